@@ -1,50 +1,538 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use wasm_bindgen::JsValue;
-use crate::log;
 use web_sys::CanvasRenderingContext2d;
 
+use crate::geometry::{Measurement, Shape, Units};
+use crate::label::LabelAnchor;
+
+
+/// 控件的状态
+/// locked: 可以看到和选中，不能拖拽和修改；visible: 是否显示；selectable: 是否响应鼠标
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ComponentFlags {
+    pub locked: bool,
+    pub visible: bool,
+    pub selectable: bool,
+}
+
+impl Default for ComponentFlags {
+    fn default() -> Self {
+        Self {
+            locked: false,
+            visible: true,
+            selectable: true,
+        }
+    }
+}
+
+impl ComponentFlags {
+    /// 鼠标可以点中
+    pub fn hittable(&self) -> bool {
+        self.visible && self.selectable
+    }
+}
+
+/// 控件的自定义属性，值是任意的 json
+pub type Metadata = BTreeMap<String, serde_json::Value>;
 
 pub trait Component {
     fn id(&self) -> u32;
     fn type_id(&self) -> u32;
     fn style(&self) -> ComponentStyle;
+    fn set_style(&mut self, style: ComponentStyle);
+
+    // 引用的样式类，样式类修改时跟着更新
+    fn style_class(&self) -> Option<&str>;
+    fn set_style_class(&mut self, class: Option<String>);
+
+    fn title(&self) -> &str;
+    fn set_title(&mut self, title: String);
+
+    fn flags(&self) -> ComponentFlags;
+    fn set_flags(&mut self, flags: ComponentFlags);
 
-    fn update_mouse(&mut self, x: i32, y: i32);
+    // 自定义的属性，例如区域的规则参数
+    fn metadata(&self) -> &Metadata;
+    fn metadata_mut(&mut self) -> &mut Metadata;
+
+    // 外部系统的 id，例如数据库记录的 uuid
+    fn external_id(&self) -> Option<&str>;
+    fn set_external_id(&mut self, external_id: Option<String>);
+    // title 的锚点，按 style.label_anchor 计算
+    fn title_position(&self) -> (f64, f64);
+
+    // 拖拽控制点，modifiers 是拖拽时按下的 Shift/Alt
+    fn update_mouse(&mut self, x: i32, y: i32, modifiers: DragModifiers);
 
     fn paint(&self, context: &CanvasRenderingContext2d);
 
 
     fn try_select(&mut self, x: i32, y: i32) -> bool;
 
+    // 点是否在控件上，线按线宽加上一点容差
+    fn contains(&self, x: f64, y: f64) -> bool;
+
+    // 几何形状，用于校验和计算
+    fn shape(&self) -> Shape;
+
+    // 几何数据，units 指定是 canvas 像素还是原图像素
+    fn bounding_box(&self, units: &Units) -> (f64, f64, f64, f64) {
+        self.shape().bounds(units)
+    }
+
+    fn area(&self, units: &Units) -> f64 {
+        self.shape().area(units)
+    }
+
+    // 线是长度
+    fn perimeter(&self, units: &Units) -> f64 {
+        self.shape().perimeter(units)
+    }
+
+    fn centroid(&self, units: &Units) -> (f64, f64) {
+        self.shape().centroid(units)
+    }
+
+    // (x, y) 是 units 的坐标
+    fn contains_point(&self, x: f64, y: f64, units: &Units) -> bool {
+        let (x, y) = units.canvas_point(x, y);
+        self.contains(x, y)
+    }
+
+    fn measure(&self, units: &Units) -> Measurement {
+        let (x, y, width, height) = self.bounding_box(units);
+        let (centroid_x, centroid_y) = self.centroid(units);
+        Measurement {
+            x,
+            y,
+            width,
+            height,
+            area: self.area(units),
+            perimeter: self.perimeter(units),
+            centroid_x,
+            centroid_y,
+        }
+    }
+
+    // 控制点坐标，拖拽不满足校验规则时用来恢复
+    fn control_points(&self) -> Vec<Point>;
+    fn set_control_points(&mut self, points: &[Point]);
+
+    // 拖拽其他控件时可以吸附的点和边
+    fn snap_points(&self) -> Vec<Point>;
+    fn snap_edges(&self) -> Vec<(Point, Point)>;
+
     fn selected(&self) -> bool;
     fn set_select(&mut self, s:bool);
+
+    // 鼠标悬停，更新 hover 状态，返回鼠标下面的内容对应的 cursor，不在控件上返回 None
+    fn hover(&mut self, x: i32, y: i32) -> Option<&'static str>;
+    fn clear_hover(&mut self);
+
+    // 是否需要持续重画，例如选中时的流动虚线
+    fn animating(&self) -> bool {
+        self.style().animating(self.selected())
+    }
+}
+
+/// 区域填充的图案
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FillPattern {
+    Solid,
+    // 交叉斜线
+    Hatch,
+    // 单向斜线
+    DiagonalStripe,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ComponentStyle {
     pub font: String,
 
     pub line_width: u32,
     pub line_color: String,
     pub line_focus_color: String,
+    pub line_hover_color: String,
+    pub line_locked_color: String,
 
     pub control_line_width: u32,
     pub control_width: u32,
     pub control_line_color: String,
     pub control_fill_color: String,
 
+    // 填充颜色，空字符串不填充
+    pub fill_color: String,
+    pub fill_opacity: f64,
+    pub fill_pattern: FillPattern,
+    // 只在选中时填充
+    pub fill_on_select: bool,
+
+    // 虚线，空数组是实线
+    pub line_dash: Vec<f64>,
+    pub line_dash_offset: f64,
+    // butt, round, square
+    pub line_cap: String,
+    // miter, round, bevel
+    pub line_join: String,
+    pub opacity: f64,
+    // 选中时虚线流动
+    pub marching_ants: bool,
+
+    pub label_anchor: LabelAnchor,
+    // title 颜色，空字符串使用线的颜色
+    pub label_color: String,
+    // title 背景框颜色，空字符串不画背景
+    pub label_background: String,
+    pub label_padding: f64,
 }
 
-#[derive(Debug, Copy, Clone)]
+impl Default for ComponentStyle {
+    fn default() -> Self {
+        Self {
+            font: "16px serif".to_string(),
+            line_width: 2,
+            line_color: "blue".to_string(),
+            line_focus_color: "red".to_string(),
+            line_hover_color: "orange".to_string(),
+            line_locked_color: "gray".to_string(),
+            control_line_width: 2,
+            control_width: 8,
+            control_line_color: "blue".to_string(),
+            control_fill_color: "red".to_string(),
+            fill_color: String::new(),
+            fill_opacity: 0.3,
+            fill_pattern: FillPattern::Solid,
+            fill_on_select: false,
+            line_dash: Vec::new(),
+            line_dash_offset: 0.0,
+            line_cap: "butt".to_string(),
+            line_join: "miter".to_string(),
+            opacity: 1.0,
+            marching_ants: false,
+            label_anchor: LabelAnchor::TopLeft,
+            label_color: String::new(),
+            label_background: "rgba(255, 255, 255, 0.7)".to_string(),
+            label_padding: 2.0,
+        }
+    }
+}
+
+impl ComponentStyle {
+    /// 只修改 patch 里设置了的字段
+    pub fn apply(&mut self, patch: ComponentStylePatch) {
+        if let Some(font) = patch.font {
+            self.font = font;
+        }
+        if let Some(line_width) = patch.line_width {
+            self.line_width = line_width;
+        }
+        if let Some(line_color) = patch.line_color {
+            self.line_color = line_color;
+        }
+        if let Some(line_focus_color) = patch.line_focus_color {
+            self.line_focus_color = line_focus_color;
+        }
+        if let Some(line_hover_color) = patch.line_hover_color {
+            self.line_hover_color = line_hover_color;
+        }
+        if let Some(line_locked_color) = patch.line_locked_color {
+            self.line_locked_color = line_locked_color;
+        }
+        if let Some(control_line_width) = patch.control_line_width {
+            self.control_line_width = control_line_width;
+        }
+        if let Some(control_width) = patch.control_width {
+            self.control_width = control_width;
+        }
+        if let Some(control_line_color) = patch.control_line_color {
+            self.control_line_color = control_line_color;
+        }
+        if let Some(control_fill_color) = patch.control_fill_color {
+            self.control_fill_color = control_fill_color;
+        }
+        if let Some(fill_color) = patch.fill_color {
+            self.fill_color = fill_color;
+        }
+        if let Some(fill_opacity) = patch.fill_opacity {
+            self.fill_opacity = fill_opacity;
+        }
+        if let Some(fill_pattern) = patch.fill_pattern {
+            self.fill_pattern = fill_pattern;
+        }
+        if let Some(fill_on_select) = patch.fill_on_select {
+            self.fill_on_select = fill_on_select;
+        }
+        if let Some(line_dash) = patch.line_dash {
+            self.line_dash = line_dash;
+        }
+        if let Some(line_dash_offset) = patch.line_dash_offset {
+            self.line_dash_offset = line_dash_offset;
+        }
+        if let Some(line_cap) = patch.line_cap {
+            self.line_cap = line_cap;
+        }
+        if let Some(line_join) = patch.line_join {
+            self.line_join = line_join;
+        }
+        if let Some(opacity) = patch.opacity {
+            self.opacity = opacity;
+        }
+        if let Some(marching_ants) = patch.marching_ants {
+            self.marching_ants = marching_ants;
+        }
+        if let Some(label_anchor) = patch.label_anchor {
+            self.label_anchor = label_anchor;
+        }
+        if let Some(label_color) = patch.label_color {
+            self.label_color = label_color;
+        }
+        if let Some(label_background) = patch.label_background {
+            self.label_background = label_background;
+        }
+        if let Some(label_padding) = patch.label_padding {
+            self.label_padding = label_padding;
+        }
+    }
+
+    /// 选中并且开启了 marching_ants，需要每一帧重画
+    pub fn animating(&self, selected: bool) -> bool {
+        selected && self.marching_ants
+    }
+
+    /// 设置线的颜色、宽度、虚线、端点、拐角和透明度
+    /// 调用方负责 save/restore
+    pub fn apply_stroke(
+        &self,
+        context: &CanvasRenderingContext2d,
+        selected: bool,
+        hover: bool,
+        locked: bool,
+    ) {
+        // 锁定的控件选中时也保持锁定的颜色
+        let line_color = if locked {
+            self.line_locked_color.as_str()
+        } else if selected {
+            self.line_focus_color.as_str()
+        } else if hover {
+            self.line_hover_color.as_str()
+        } else {
+            self.line_color.as_str()
+        };
+
+        context.set_stroke_style_str(line_color);
+        context.set_line_width(self.line_width as f64);
+        context.set_line_cap(self.line_cap.as_str());
+        context.set_line_join(self.line_join.as_str());
+        context.set_global_alpha(self.opacity);
+
+        let mut line_dash = self.line_dash.clone();
+        let mut line_dash_offset = self.line_dash_offset;
+        if self.animating(selected) {
+            if line_dash.is_empty() {
+                line_dash = vec![6.0, 4.0];
+            }
+            // 按时间移动 offset，虚线看起来在流动
+            let dash_length: f64 = line_dash.iter().sum();
+            if dash_length > 0.0 {
+                line_dash_offset -= (js_sys::Date::now() / 40.0) % dash_length;
+            }
+        }
+
+        let segments = line_dash
+            .iter()
+            .map(|v| JsValue::from_f64(*v))
+            .collect::<js_sys::Array>();
+        let _ = context.set_line_dash(&segments);
+        context.set_line_dash_offset(line_dash_offset);
+    }
+
+    /// 填充当前 path，bounds 是 path 的外接矩形 (x, y, width, height)，用来画斜线图案
+    /// 斜线画在单独的 Path2d 上，当前 path 保持不变，填充之后调用方还可以继续 stroke 形状的边
+    pub fn fill_path(
+        &self,
+        context: &CanvasRenderingContext2d,
+        selected: bool,
+        bounds: (f64, f64, f64, f64),
+    ) {
+        if self.fill_color.is_empty() || (self.fill_on_select && !selected) {
+            return;
+        }
+
+        context.save();
+        // 和线的透明度叠加
+        context.set_global_alpha(context.global_alpha() * self.fill_opacity);
+        match self.fill_pattern {
+            FillPattern::Solid => {
+                context.set_fill_style_str(self.fill_color.as_str());
+                context.fill();
+            }
+            FillPattern::Hatch | FillPattern::DiagonalStripe => {
+                let spacing = 8_f64;
+                let (x, y, width, height) = bounds;
+
+                // 斜线放在单独的 Path2d 里，不替换调用方的当前 path
+                let stripes = match web_sys::Path2d::new() {
+                    Ok(stripes) => stripes,
+                    Err(_) => {
+                        context.restore();
+                        return;
+                    }
+                };
+
+                // 斜线从外接矩形左下方扫到右上方
+                let mut offset = -height;
+                while offset <= width {
+                    stripes.move_to(x + offset, y + height);
+                    stripes.line_to(x + offset + height, y);
+                    if self.fill_pattern == FillPattern::Hatch {
+                        stripes.move_to(x + offset, y);
+                        stripes.line_to(x + offset + height, y + height);
+                    }
+                    offset += spacing;
+                }
+
+                // 按当前 path 裁剪，斜线不会画到形状外面
+                context.clip();
+                context.set_stroke_style_str(self.fill_color.as_str());
+                context.set_line_width(1.0);
+                let _ = context.set_line_dash(&js_sys::Array::new());
+                context.stroke_with_path(&stripes);
+            }
+        }
+        context.restore();
+    }
+}
+
+/// js 传入的部分样式，没有的字段保持不变
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ComponentStylePatch {
+    pub font: Option<String>,
+
+    pub line_width: Option<u32>,
+    pub line_color: Option<String>,
+    pub line_focus_color: Option<String>,
+    pub line_hover_color: Option<String>,
+    pub line_locked_color: Option<String>,
+
+    pub control_line_width: Option<u32>,
+    pub control_width: Option<u32>,
+    pub control_line_color: Option<String>,
+    pub control_fill_color: Option<String>,
+
+    pub fill_color: Option<String>,
+    pub fill_opacity: Option<f64>,
+    pub fill_pattern: Option<FillPattern>,
+    pub fill_on_select: Option<bool>,
+
+    pub line_dash: Option<Vec<f64>>,
+    pub line_dash_offset: Option<f64>,
+    pub line_cap: Option<String>,
+    pub line_join: Option<String>,
+    pub opacity: Option<f64>,
+    pub marching_ants: Option<bool>,
+
+    pub label_anchor: Option<LabelAnchor>,
+    pub label_color: Option<String>,
+    pub label_background: Option<String>,
+    pub label_padding: Option<f64>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Point {
     pub x: i32,
     pub y: i32,
 }
 
+impl Point {
+    fn middle(&self, point: Point) -> Point {
+        Point {
+            x: (self.x + point.x) / 2,
+            y: (self.y + point.y) / 2,
+        }
+    }
+}
+
+/// 拖拽时按下的功能键
+/// shift: 矩形保持正方形，直线/圆的半径限制在 0/45/90 度
+/// alt: 以中心为基准缩放，而不是对角/另一端
+#[derive(Debug, Copy, Clone, Default)]
+pub struct DragModifiers {
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl DragModifiers {
+    pub fn from_event(event: &web_sys::MouseEvent) -> Self {
+        Self {
+            shift: event.shift_key(),
+            alt: event.alt_key(),
+        }
+    }
+}
+
+// 以 anchor 为基准，point 限制成正方形的对角
+fn square_point(anchor: Point, point: Point) -> Point {
+    let dx = point.x - anchor.x;
+    let dy = point.y - anchor.y;
+    let side = dx.abs().max(dy.abs());
+    let sign = |d: i32| if d < 0 { -1 } else { 1 };
+    Point {
+        x: anchor.x + sign(dx) * side,
+        y: anchor.y + sign(dy) * side,
+    }
+}
+
+// 以 anchor 为基准，point 的方向限制到最近的 45 度倍数，长度不变
+fn snap_angle_point(anchor: Point, point: Point) -> Point {
+    let dx = (point.x - anchor.x) as f64;
+    let dy = (point.y - anchor.y) as f64;
+    let length = dx.hypot(dy);
+    let step = std::f64::consts::FRAC_PI_4;
+    let angle = (dy.atan2(dx) / step).round() * step;
+    Point {
+        x: anchor.x + (length * angle.cos()).round() as i32,
+        y: anchor.y + (length * angle.sin()).round() as i32,
+    }
+}
+
+// 拖拽 moving 控制点到 (x, y)，other 是另一个控制点
+// alt 时以两点中点为中心，other 跟着对称移动；constrain 对拖拽点做限制
+fn drag_pair(
+    moving: &mut Point,
+    other: &mut Point,
+    x: i32,
+    y: i32,
+    alt: bool,
+    constrain: Option<fn(Point, Point) -> Point>,
+) {
+    let point = Point { x, y };
+    let anchor = if alt { moving.middle(*other) } else { *other };
+    let point = match constrain {
+        Some(constrain) => constrain(anchor, point),
+        None => point,
+    };
+
+    if alt {
+        // 用两点坐标和计算对称点，避免中点取整后越拖越偏
+        *other = Point {
+            x: moving.x + other.x - point.x,
+            y: moving.y + other.y - point.y,
+        };
+    }
+    *moving = point;
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct ControlPoint {
     pub point: Point,
     pub width: u32,
     pub selected: bool,
+    pub hover: bool,
 }
 
 impl ControlPoint {
@@ -53,6 +541,7 @@ impl ControlPoint {
             point: Point { x, y },
             width,
             selected: false,
+            hover: false,
         }
     }
 
@@ -63,7 +552,7 @@ impl ControlPoint {
         let bottom = self.point.y + (self.width / 2) as i32;
 
         let ret = x >= left && x <= right && y >= top && y <= bottom;
-        log(&format!("{} {} {} {}, {} {}, {}",left,right,top,bottom,x,y,ret));
+        log_trace!("{} {} {} {}, {} {}, {}",left,right,top,bottom,x,y,ret);
 
         ret
 
@@ -71,65 +560,140 @@ impl ControlPoint {
 
 
     fn paint(&self, context: &CanvasRenderingContext2d, style: &ComponentStyle) {
-        let left = self.point.x - (self.width / 2) as i32;
-        let top = self.point.y - (self.width / 2) as i32;
+        // hover 时画大一点
+        let width = if self.hover { self.width + 4 } else { self.width };
+        let left = self.point.x - (width / 2) as i32;
+        let top = self.point.y - (width / 2) as i32;
 
         context.set_line_width(style.control_line_width as f64);
-        context.set_fill_style(&JsValue::from_str(style.control_fill_color.as_str()));
+        context.set_fill_style_str(style.control_fill_color.as_str());
         context.fill_rect(left as f64,
                           top as f64,
-                          self.width as f64,
-                          self.width as f64, );
+                          width as f64,
+                          width as f64, );
 
-        context.set_stroke_style(&JsValue::from_str(style.control_line_color.as_str()));
+        context.set_stroke_style_str(style.control_line_color.as_str());
         // 画矩形框
         context.stroke_rect(
             left as f64,
             top as f64,
-            self.width as f64,
-            self.width as f64,
+            width as f64,
+            width as f64,
         );
     }
 }
 
+// 矩形控制点的位置: (x 方向, y 方向)，-1 是左/上边，1 是右/下边，0 是中点
+// 前四个是角，顺时针；后四个是边的中点，上右下左
+const RECT_HANDLES: [(i32, i32); 8] = [
+    (-1, -1),
+    (1, -1),
+    (1, 1),
+    (-1, 1),
+    (0, -1),
+    (1, 0),
+    (0, 1),
+    (-1, 0),
+];
+
+/// 矩形拖拽开始时记录的状态，拖拽过程中按它计算，不受中途翻转影响
+#[derive(Debug, Copy, Clone)]
+struct RectDrag {
+    sides: (i32, i32),
+    // 对边/对角的坐标
+    anchor: Point,
+    // 中心点坐标的 2 倍，避免取整
+    center2: Point,
+}
+
 pub struct RectComponent {
     pub id: u32,
     pub style: ComponentStyle,
+    pub style_class: Option<String>,
+    pub external_id: Option<String>,
+    pub metadata: Metadata,
+    pub flags: ComponentFlags,
 
-    pub width: u32,
-    pub height: u32,
+    // 始终保持 left_top 在左上，right_bottom 在右下
+    pub left_top: Point,
+    pub right_bottom: Point,
 
     pub title: String,
 
-    pub start_control: ControlPoint,
-    pub end_control: ControlPoint,
+    // 四个角和四条边中点的控制点，顺序和 RECT_HANDLES 一致
+    pub handles: [ControlPoint; 8],
+    drag: Option<RectDrag>,
 
     pub selected: bool,
+    pub hover: bool,
 
 }
 
 impl RectComponent {
-    fn lt_point(&self) -> (f64, f64) {
-        let start = self.start_control.point;
-        let end = self.end_control.point;
-        match (start.x >= end.x, start.y >= end.y) {
-            (false, false) => (start.x as f64, start.y as f64),
-            (true, true) => (end.x as f64, end.y as f64),
-            (true, false) => (end.x as f64, start.y as f64),
-            (false, true) => (start.x as f64, end.y as f64),
-        }
+    pub fn new(
+        id: u32,
+        left_top: Point,
+        right_bottom: Point,
+        style: &ComponentStyle,
+        title: String,
+    ) -> Self {
+        let handle = ControlPoint::new(0, 0, style.control_width);
+        let mut comp = RectComponent {
+            id,
+            style: style.clone(),
+            style_class: None,
+            external_id: None,
+            metadata: Metadata::new(),
+            flags: ComponentFlags::default(),
+            left_top,
+            right_bottom,
+            title,
+            handles: [handle; 8],
+            drag: None,
+            selected: false,
+            hover: false,
+        };
+        comp.normalize();
+        comp
     }
 
-    pub fn title_position(&self) -> (f64, f64) {
-        let (x, y) = self.lt_point();
+    fn width(&self) -> f64 {
+        (self.right_bottom.x - self.left_top.x) as f64
+    }
 
-        (x + (self.width / 2) as f64, y)
+    fn height(&self) -> f64 {
+        (self.right_bottom.y - self.left_top.y) as f64
     }
 
-    fn re_calculate(&mut self) {
-        self.width = (self.end_control.point.x - self.start_control.point.x).abs() as u32;
-        self.height = (self.end_control.point.y - self.start_control.point.y).abs() as u32;
+    // 保证左上/右下的顺序，然后重新放置控制点
+    fn normalize(&mut self) {
+        let (lt, rb) = (self.left_top, self.right_bottom);
+        self.left_top = Point {
+            x: lt.x.min(rb.x),
+            y: lt.y.min(rb.y),
+        };
+        self.right_bottom = Point {
+            x: lt.x.max(rb.x),
+            y: lt.y.max(rb.y),
+        };
+
+        let (lt, rb) = (self.left_top, self.right_bottom);
+        let position = |side: i32, low: i32, high: i32| match side {
+            -1 => low,
+            1 => high,
+            _ => (low + high) / 2,
+        };
+        for (handle, (sx, sy)) in self.handles.iter_mut().zip(RECT_HANDLES) {
+            handle.point = Point {
+                x: position(sx, lt.x, rb.x),
+                y: position(sy, lt.y, rb.y),
+            };
+        }
+    }
 
+    // 控制点在 x 或 y 方向上的新范围，fixed 是不动的那条边
+    fn span(moving: i32, fixed: i32) -> (i32, i32) {
+        (moving.min(fixed), moving.max(fixed))
     }
 }
 
@@ -146,64 +710,223 @@ impl Component for RectComponent {
         self.style.clone()
     }
 
-    fn update_mouse(&mut self, x: i32, y: i32) {
-        if self.start_control.selected {
-            self.start_control.point.x = x;
-            self.start_control.point.y = y;
-        } else if self.end_control.selected {
-            self.end_control.point.x = x;
-            self.end_control.point.y = y;
+    fn set_style(&mut self, style: ComponentStyle) {
+        for handle in self.handles.iter_mut() {
+            handle.width = style.control_width;
         }
-        self.re_calculate();
+        self.style = style;
     }
 
-    fn paint(&self, context: &CanvasRenderingContext2d) {
+    fn style_class(&self) -> Option<&str> {
+        self.style_class.as_deref()
+    }
 
-        let line_color = if self.selected {
-            self.style.line_focus_color.as_str()
-        } else{
-            self.style.line_color.as_str()
+    fn set_style_class(&mut self, class: Option<String>) {
+        self.style_class = class;
+    }
+
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn set_title(&mut self, title: String) {
+        self.title = title;
+    }
+
+    fn external_id(&self) -> Option<&str> {
+        self.external_id.as_deref()
+    }
+
+    fn set_external_id(&mut self, external_id: Option<String>) {
+        self.external_id = external_id;
+    }
+
+    fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    fn metadata_mut(&mut self) -> &mut Metadata {
+        &mut self.metadata
+    }
+
+    fn flags(&self) -> ComponentFlags {
+        self.flags
+    }
+
+    fn set_flags(&mut self, flags: ComponentFlags) {
+        self.flags = flags;
+    }
+
+    fn title_position(&self) -> (f64, f64) {
+        let (x, y) = (self.left_top.x as f64, self.left_top.y as f64);
+        let (width, height) = (self.width(), self.height());
+        match self.style.label_anchor {
+            LabelAnchor::TopLeft => (x, y),
+            LabelAnchor::Center => (x + width / 2_f64, y + height / 2_f64),
+            LabelAnchor::AboveMidpoint => (x + width / 2_f64, y),
+        }
+    }
+
+    fn update_mouse(&mut self, x: i32, y: i32, modifiers: DragModifiers) {
+        let drag = match self.drag {
+            Some(drag) => drag,
+            None => return,
+        };
+        let (sx, sy) = drag.sides;
+        let center = Point {
+            x: drag.center2.x / 2,
+            y: drag.center2.y / 2,
         };
 
-        // 设置线颜色和宽带
-        context.set_stroke_style(&JsValue::from_str(line_color));
-        context.set_line_width(self.style.line_width as f64);
+        // shift 只对角上的控制点生效
+        let mut point = Point { x, y };
+        if modifiers.shift && sx != 0 && sy != 0 {
+            let anchor = if modifiers.alt { center } else { drag.anchor };
+            point = square_point(anchor, point);
+        }
 
-        // 画矩形框
-        let (lt_x, lt_y) = self.lt_point();
-        context.stroke_rect(lt_x, lt_y, self.width as f64, self.height as f64);
+        // alt 时对边按中心对称移动
+        let fixed = if modifiers.alt {
+            Point {
+                x: drag.center2.x - point.x,
+                y: drag.center2.y - point.y,
+            }
+        } else {
+            drag.anchor
+        };
+
+        let (mut lt, mut rb) = (self.left_top, self.right_bottom);
+        if sx != 0 {
+            (lt.x, rb.x) = Self::span(point.x, fixed.x);
+        }
+        if sy != 0 {
+            (lt.y, rb.y) = Self::span(point.y, fixed.y);
+        }
+        self.left_top = lt;
+        self.right_bottom = rb;
+        self.normalize();
+
+        // 拖过对边之后，高亮翻转后对应的控制点
+        let flip = |side: i32, moving: i32, fixed: i32| match side {
+            0 => 0,
+            _ if moving < fixed => -1,
+            _ => 1,
+        };
+        let sides = (flip(sx, point.x, fixed.x), flip(sy, point.y, fixed.y));
+        for (handle, handle_sides) in self.handles.iter_mut().zip(RECT_HANDLES) {
+            handle.selected = handle_sides == sides;
+        }
+    }
 
-        // 画控制点
-        self.start_control.paint(context, &self.style);
-        self.end_control.paint(context, &self.style);
+    fn paint(&self, context: &CanvasRenderingContext2d) {
 
-        // 设置字体相关信息
-        context.set_fill_style(&JsValue::from_str(self.style.line_color.as_str()));
-        context.set_text_baseline("middle");
-        context.set_text_align("cetner");
-        context.set_font(self.style.font.as_str());
+        // 设置线颜色、宽带和虚线，控制点和 title 不受影响
+        context.save();
+        self.style.apply_stroke(context, self.selected, self.hover, self.flags.locked);
+
+        // 画矩形框
+        let (lt_x, lt_y) = (self.left_top.x as f64, self.left_top.y as f64);
+        let (width, height) = (self.width(), self.height());
+        context.begin_path();
+        context.rect(lt_x, lt_y, width, height);
+        self.style.fill_path(context, self.selected, (lt_x, lt_y, width, height));
+        context.stroke();
+        context.restore();
 
-        // 画 title
-        let (title_x, title_y) = self.title_position();
-        let title_offset = 16.00;
-        context.fill_text(&self.title, title_x - title_offset, title_y + title_offset).unwrap();
+        // 画控制点，锁定的控件不画
+        if !self.flags.locked {
+            for handle in self.handles.iter() {
+                handle.paint(context, &self.style);
+            }
+        }
     }
 
 
     fn try_select(&mut self, x: i32, y: i32) -> bool {
-        if self.start_control.can_select(x,y) {
-            self.start_control.selected = true;
-            return true;
+        let index = match self.handles.iter().position(|handle| handle.can_select(x, y)) {
+            Some(index) => index,
+            None => return false,
+        };
+
+        let (sx, sy) = RECT_HANDLES[index];
+        let (lt, rb) = (self.left_top, self.right_bottom);
+        self.drag = Some(RectDrag {
+            sides: (sx, sy),
+            anchor: Point {
+                x: if sx < 0 { rb.x } else { lt.x },
+                y: if sy < 0 { rb.y } else { lt.y },
+            },
+            center2: Point {
+                x: lt.x + rb.x,
+                y: lt.y + rb.y,
+            },
+        });
+        self.handles[index].selected = true;
+        true
+    }
+
+
+    fn contains(&self, x: f64, y: f64) -> bool {
+        let (lt, rb) = (self.left_top, self.right_bottom);
+        x >= lt.x as f64 && x <= rb.x as f64 && y >= lt.y as f64 && y <= rb.y as f64
+    }
+
+    fn shape(&self) -> Shape {
+        Shape::Rect {
+            x: self.left_top.x as f64,
+            y: self.left_top.y as f64,
+            width: self.width(),
+            height: self.height(),
         }
+    }
 
-        if self.end_control.can_select(x,y) {
-            self.end_control.selected = true;
-            return true;
+    fn control_points(&self) -> Vec<Point> {
+        vec![self.left_top, self.right_bottom]
+    }
+
+    fn set_control_points(&mut self, points: &[Point]) {
+        if let [left_top, right_bottom] = points {
+            self.left_top = *left_top;
+            self.right_bottom = *right_bottom;
         }
+        self.normalize();
+    }
 
-        false
+    fn snap_points(&self) -> Vec<Point> {
+        // 四个角
+        self.handles[..4].iter().map(|handle| handle.point).collect()
+    }
+
+    fn snap_edges(&self) -> Vec<(Point, Point)> {
+        let points = self.snap_points();
+        (0..points.len())
+            .map(|i| (points[i], points[(i + 1) % points.len()]))
+            .collect()
+    }
+
+    fn hover(&mut self, x: i32, y: i32) -> Option<&'static str> {
+        let index = self.handles.iter().position(|handle| handle.can_select(x, y));
+        for (i, handle) in self.handles.iter_mut().enumerate() {
+            handle.hover = Some(i) == index;
+        }
+        self.hover = index.is_some() || self.contains(x as f64, y as f64);
+
+        match index.map(|index| RECT_HANDLES[index]) {
+            Some((-1, -1)) | Some((1, 1)) => Some("nwse-resize"),
+            Some((1, -1)) | Some((-1, 1)) => Some("nesw-resize"),
+            Some((0, _)) => Some("ns-resize"),
+            Some(_) => Some("ew-resize"),
+            None if self.hover => Some("pointer"),
+            None => None,
+        }
     }
 
+    fn clear_hover(&mut self) {
+        self.hover = false;
+        for handle in self.handles.iter_mut() {
+            handle.hover = false;
+        }
+    }
 
     fn selected(&self) -> bool {
         self.selected
@@ -212,8 +935,10 @@ impl Component for RectComponent {
     fn set_select(&mut self,s: bool) {
         self.selected = s;
         if !self.selected {
-            self.start_control.selected = false;
-            self.end_control.selected = false;
+            self.drag = None;
+            for handle in self.handles.iter_mut() {
+                handle.selected = false;
+            }
         }
 
     }
@@ -223,6 +948,10 @@ impl Component for RectComponent {
 pub struct LineComponent {
     pub id: u32,
     pub style: ComponentStyle,
+    pub style_class: Option<String>,
+    pub external_id: Option<String>,
+    pub metadata: Metadata,
+    pub flags: ComponentFlags,
 
     pub title: String,
 
@@ -230,14 +959,25 @@ pub struct LineComponent {
     pub end_control: ControlPoint,
 
     pub selected: bool,
+    pub hover: bool,
 
 }
 
 impl LineComponent {
-    pub fn title_position(&self) -> (f64, f64) {
-        let x = self.start_control.point.x + (self.end_control.point.x - self.start_control.point.x) / 2;
-        let y = self.start_control.point.y + (self.end_control.point.y - self.start_control.point.y) / 2;
-        (x as f64, y as f64)
+    pub fn new(id: u32, start: Point, end: Point, style: &ComponentStyle, title: String) -> Self {
+        LineComponent {
+            id,
+            style: style.clone(),
+            style_class: None,
+            external_id: None,
+            metadata: Metadata::new(),
+            flags: ComponentFlags::default(),
+            title,
+            start_control: ControlPoint::new(start.x, start.y, style.control_width),
+            end_control: ControlPoint::new(end.x, end.y, style.control_width),
+            selected: false,
+            hover: false,
+        }
     }
 }
 
@@ -254,27 +994,84 @@ impl Component for LineComponent {
         self.style.clone()
     }
 
-    fn update_mouse(&mut self, x: i32, y: i32) {
+    fn set_style(&mut self, style: ComponentStyle) {
+        self.start_control.width = style.control_width;
+        self.end_control.width = style.control_width;
+        self.style = style;
+    }
+
+    fn style_class(&self) -> Option<&str> {
+        self.style_class.as_deref()
+    }
+
+    fn set_style_class(&mut self, class: Option<String>) {
+        self.style_class = class;
+    }
+
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn set_title(&mut self, title: String) {
+        self.title = title;
+    }
+
+    fn external_id(&self) -> Option<&str> {
+        self.external_id.as_deref()
+    }
+
+    fn set_external_id(&mut self, external_id: Option<String>) {
+        self.external_id = external_id;
+    }
+
+    fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    fn metadata_mut(&mut self) -> &mut Metadata {
+        &mut self.metadata
+    }
+
+    fn flags(&self) -> ComponentFlags {
+        self.flags
+    }
+
+    fn set_flags(&mut self, flags: ComponentFlags) {
+        self.flags = flags;
+    }
+
+    fn title_position(&self) -> (f64, f64) {
+        let start = self.start_control.point;
+        let end = self.end_control.point;
+        match self.style.label_anchor {
+            LabelAnchor::TopLeft => (start.x.min(end.x) as f64, start.y.min(end.y) as f64),
+            LabelAnchor::Center | LabelAnchor::AboveMidpoint => (
+                (start.x + end.x) as f64 / 2_f64,
+                (start.y + end.y) as f64 / 2_f64,
+            ),
+        }
+    }
+
+    fn update_mouse(&mut self, x: i32, y: i32, modifiers: DragModifiers) {
+        let constrain = if modifiers.shift {
+            Some(snap_angle_point as fn(Point, Point) -> Point)
+        } else {
+            None
+        };
+
+        let (start, end) = (&mut self.start_control.point, &mut self.end_control.point);
         if self.start_control.selected {
-            self.start_control.point.x = x;
-            self.start_control.point.y = y;
+            drag_pair(start, end, x, y, modifiers.alt, constrain);
         } else if self.end_control.selected {
-            self.end_control.point.x = x;
-            self.end_control.point.y = y;
+            drag_pair(end, start, x, y, modifiers.alt, constrain);
         }
     }
 
     fn paint(&self, context: &CanvasRenderingContext2d) {
 
-        let line_color = if self.selected {
-            self.style.line_focus_color.as_str()
-        } else{
-            self.style.line_color.as_str()
-        };
-
-        // 设置线颜色和宽带
-        context.set_stroke_style(&JsValue::from_str(line_color));
-        context.set_line_width(self.style.line_width as f64);
+        // 设置线颜色、宽带和虚线，控制点和 title 不受影响
+        context.save();
+        self.style.apply_stroke(context, self.selected, self.hover, self.flags.locked);
 
         // 画直线
         context.begin_path();
@@ -284,21 +1081,13 @@ impl Component for LineComponent {
         );
         context.line_to(self.end_control.point.x as f64, self.end_control.point.y as f64);
         context.stroke();
+        context.restore();
 
-        // 画控制点
-        self.start_control.paint(context, &self.style);
-        self.end_control.paint(context, &self.style);
-
-        // 设置字体相关信息
-        context.set_fill_style(&JsValue::from_str(self.style.line_color.as_str()));
-        context.set_text_baseline("middle");
-        context.set_text_align("cetner");
-        context.set_font(self.style.font.as_str());
-
-        // 画 title
-        let (title_x, title_y) = self.title_position();
-        let title_offset = 4.00;
-        context.fill_text(&self.title, title_x + title_offset, title_y + title_offset).unwrap();
+        // 画控制点，锁定的控件不画
+        if !self.flags.locked {
+            self.start_control.paint(context, &self.style);
+            self.end_control.paint(context, &self.style);
+        }
     }
 
 
@@ -316,6 +1105,72 @@ impl Component for LineComponent {
         false
     }
 
+    fn contains(&self, x: f64, y: f64) -> bool {
+        let (x1, y1) = (self.start_control.point.x as f64, self.start_control.point.y as f64);
+        let (x2, y2) = (self.end_control.point.x as f64, self.end_control.point.y as f64);
+        let (dx, dy) = (x2 - x1, y2 - y1);
+
+        // 点到线段的最近点
+        let length = dx * dx + dy * dy;
+        let t = if length == 0_f64 {
+            0_f64
+        } else {
+            (((x - x1) * dx + (y - y1) * dy) / length).clamp(0_f64, 1_f64)
+        };
+        let (px, py) = (x1 + t * dx, y1 + t * dy);
+        let dist = ((x - px).powi(2) + (y - py).powi(2)).sqrt();
+
+        dist <= self.style.line_width as f64 / 2_f64 + 4_f64
+    }
+
+    fn shape(&self) -> Shape {
+        Shape::Line {
+            start: self.start_control.point,
+            end: self.end_control.point,
+        }
+    }
+
+    fn control_points(&self) -> Vec<Point> {
+        vec![self.start_control.point, self.end_control.point]
+    }
+
+    fn set_control_points(&mut self, points: &[Point]) {
+        if let [start, end] = points {
+            self.start_control.point = *start;
+            self.end_control.point = *end;
+        }
+    }
+
+    fn snap_points(&self) -> Vec<Point> {
+        vec![self.start_control.point, self.end_control.point]
+    }
+
+    fn snap_edges(&self) -> Vec<(Point, Point)> {
+        vec![(self.start_control.point, self.end_control.point)]
+    }
+
+    fn hover(&mut self, x: i32, y: i32) -> Option<&'static str> {
+        self.start_control.hover = self.start_control.can_select(x, y);
+        self.end_control.hover = !self.start_control.hover && self.end_control.can_select(x, y);
+        self.hover = self.start_control.hover
+            || self.end_control.hover
+            || self.contains(x as f64, y as f64);
+
+        if self.start_control.hover || self.end_control.hover {
+            Some("crosshair")
+        } else if self.hover {
+            Some("pointer")
+        } else {
+            None
+        }
+    }
+
+    fn clear_hover(&mut self) {
+        self.hover = false;
+        self.start_control.hover = false;
+        self.end_control.hover = false;
+    }
+
     fn selected(&self) -> bool {
         self.selected
     }
@@ -334,6 +1189,10 @@ impl Component for LineComponent {
 pub struct CircleComponent {
     pub id: u32,
     pub style: ComponentStyle,
+    pub style_class: Option<String>,
+    pub external_id: Option<String>,
+    pub metadata: Metadata,
+    pub flags: ComponentFlags,
 
     pub title: String,
 
@@ -343,20 +1202,50 @@ pub struct CircleComponent {
     pub radius: u32,
 
     pub selected: bool,
+    pub hover: bool,
 
 }
 
 impl CircleComponent {
-    pub fn title_position(&self) -> (f64, f64) {
-        let x = self.start_control.point.x + (self.end_control.point.x - self.start_control.point.x) / 2;
-        let y = self.start_control.point.y + (self.end_control.point.y - self.start_control.point.y) / 2;
-        (x as f64, y as f64)
+    // handle 是半径控制点，不在圆周上时按方向放到圆周上
+    pub fn new(id: u32, center: Point, handle: Point, style: &ComponentStyle, title: String) -> Self {
+        let mut comp = CircleComponent {
+            id,
+            style: style.clone(),
+            style_class: None,
+            external_id: None,
+            metadata: Metadata::new(),
+            flags: ComponentFlags::default(),
+            title,
+            start_control: ControlPoint::new(center.x, center.y, style.control_width),
+            end_control: ControlPoint::new(handle.x, handle.y, style.control_width),
+            radius: 0,
+            selected: false,
+            hover: false,
+        };
+        comp.re_calculate();
+        comp
     }
 
     pub fn re_calculate(&mut self) {
-        let dist  = (self.start_control.point.x - self.end_control.point.x).pow(2)
-            + (self.start_control.point.y - self.end_control.point.y).pow(2);
-        self.radius = (dist as f64).sqrt() as u32;
+        let toward = self.end_control.point;
+        self.place_handle(toward);
+    }
+
+    // 半径控制点放到圆心指向 toward 的方向上，半径取 toward 到圆心的距离
+    // 保证控制点始终在圆周上
+    fn place_handle(&mut self, toward: Point) {
+        let center = self.start_control.point;
+        let (dx, dy) = ((toward.x - center.x) as f64, (toward.y - center.y) as f64);
+        let dist = dx.hypot(dy);
+        self.radius = dist.round() as u32;
+
+        let radius = self.radius as f64;
+        let (ux, uy) = if dist > 0_f64 { (dx / dist, dy / dist) } else { (1_f64, 0_f64) };
+        self.end_control.point = Point {
+            x: center.x + (ux * radius).round() as i32,
+            y: center.y + (uy * radius).round() as i32,
+        };
     }
 }
 
@@ -373,56 +1262,115 @@ impl Component for CircleComponent {
         self.style.clone()
     }
 
-    fn update_mouse(&mut self, x: i32, y: i32) {
+    fn set_style(&mut self, style: ComponentStyle) {
+        self.start_control.width = style.control_width;
+        self.end_control.width = style.control_width;
+        self.style = style;
+    }
+
+    fn style_class(&self) -> Option<&str> {
+        self.style_class.as_deref()
+    }
+
+    fn set_style_class(&mut self, class: Option<String>) {
+        self.style_class = class;
+    }
+
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn set_title(&mut self, title: String) {
+        self.title = title;
+    }
+
+    fn external_id(&self) -> Option<&str> {
+        self.external_id.as_deref()
+    }
+
+    fn set_external_id(&mut self, external_id: Option<String>) {
+        self.external_id = external_id;
+    }
+
+    fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    fn metadata_mut(&mut self) -> &mut Metadata {
+        &mut self.metadata
+    }
+
+    fn flags(&self) -> ComponentFlags {
+        self.flags
+    }
+
+    fn set_flags(&mut self, flags: ComponentFlags) {
+        self.flags = flags;
+    }
+
+    fn title_position(&self) -> (f64, f64) {
+        let (x, y) = (self.start_control.point.x as f64, self.start_control.point.y as f64);
+        let radius = self.radius as f64;
+        match self.style.label_anchor {
+            LabelAnchor::TopLeft => (x - radius, y - radius),
+            LabelAnchor::Center => (x, y),
+            LabelAnchor::AboveMidpoint => (x, y - radius),
+        }
+    }
+
+    fn update_mouse(&mut self, x: i32, y: i32, modifiers: DragModifiers) {
+        // 圆本身就是以圆心为中心缩放，alt 不起作用
+        // shift 把半径控制点限制在 0/45/90 度方向上
         if self.start_control.selected {
-            self.start_control.point.x = x;
-            self.start_control.point.y = y;
+            // 拖拽圆心时整体平移，半径不变
+            let center = self.start_control.point;
+            let (dx, dy) = (x - center.x, y - center.y);
+            self.start_control.point = Point { x, y };
+            self.end_control.point.x += dx;
+            self.end_control.point.y += dy;
         } else if self.end_control.selected {
-            self.end_control.point.x = x;
-            self.end_control.point.y = y;
+            let point = Point { x, y };
+            let toward = if modifiers.shift {
+                snap_angle_point(self.start_control.point, point)
+            } else {
+                point
+            };
+            self.place_handle(toward);
         }
-        self.re_calculate();
 
     }
 
     fn paint(&self, context: &CanvasRenderingContext2d) {
 
-        let line_color = if self.selected {
-            self.style.line_focus_color.as_str()
-        } else{
-            self.style.line_color.as_str()
-        };
-
-        // 设置线颜色和宽带
-        context.set_stroke_style(&JsValue::from_str(line_color));
-        context.set_line_width(self.style.line_width as f64);
+        // 设置线颜色、宽带和虚线，控制点和 title 不受影响
+        context.save();
+        self.style.apply_stroke(context, self.selected, self.hover, self.flags.locked);
 
         // 画直线
         context.begin_path();
 
-        context.arc(
-            self.start_control.point.x as f64,
-            self.start_control.point.y as f64,
-            self.radius as f64,
+        let (x, y) = (self.start_control.point.x as f64, self.start_control.point.y as f64);
+        let radius = self.radius as f64;
+        let _ = context.arc(
+            x,
+            y,
+            radius,
             0.0,
             2_f64 * std::f64::consts::PI,
-        ).unwrap();
+        );
+        self.style.fill_path(
+            context,
+            self.selected,
+            (x - radius, y - radius, radius * 2_f64, radius * 2_f64),
+        );
         context.stroke();
+        context.restore();
 
-        // 画控制点
-        self.start_control.paint(context, &self.style);
-        self.end_control.paint(context, &self.style);
-
-        // 设置字体相关信息
-        context.set_fill_style(&JsValue::from_str(self.style.line_color.as_str()));
-        context.set_text_baseline("middle");
-        context.set_text_align("cetner");
-        context.set_font(self.style.font.as_str());
-
-        // 画 title
-        let (title_x, title_y) = self.title_position();
-        let title_offset = 16.00;
-        context.fill_text(&self.title, title_x - title_offset, title_y + title_offset).unwrap();
+        // 画控制点，锁定的控件不画
+        if !self.flags.locked {
+            self.start_control.paint(context, &self.style);
+            self.end_control.paint(context, &self.style);
+        }
     }
 
 
@@ -440,6 +1388,73 @@ impl Component for CircleComponent {
         false
     }
 
+    fn contains(&self, x: f64, y: f64) -> bool {
+        let dx = x - self.start_control.point.x as f64;
+        let dy = y - self.start_control.point.y as f64;
+        (dx * dx + dy * dy).sqrt() <= self.radius as f64
+    }
+
+    fn shape(&self) -> Shape {
+        Shape::Circle {
+            center: self.start_control.point,
+            radius: self.radius as f64,
+        }
+    }
+
+    fn control_points(&self) -> Vec<Point> {
+        vec![self.start_control.point, self.end_control.point]
+    }
+
+    fn set_control_points(&mut self, points: &[Point]) {
+        if let [start, end] = points {
+            self.start_control.point = *start;
+            self.end_control.point = *end;
+        }
+        self.re_calculate();
+    }
+
+    fn snap_points(&self) -> Vec<Point> {
+        // 圆心和上下左右四个点
+        let center = self.start_control.point;
+        let radius = self.radius as i32;
+        vec![
+            center,
+            Point { x: center.x + radius, y: center.y },
+            Point { x: center.x, y: center.y + radius },
+            Point { x: center.x - radius, y: center.y },
+            Point { x: center.x, y: center.y - radius },
+        ]
+    }
+
+    fn snap_edges(&self) -> Vec<(Point, Point)> {
+        Vec::new()
+    }
+
+    fn hover(&mut self, x: i32, y: i32) -> Option<&'static str> {
+        self.start_control.hover = self.start_control.can_select(x, y);
+        self.end_control.hover = !self.start_control.hover && self.end_control.can_select(x, y);
+        self.hover = self.start_control.hover
+            || self.end_control.hover
+            || self.contains(x as f64, y as f64);
+
+        if self.start_control.hover {
+            // 圆心
+            Some("move")
+        } else if self.end_control.hover {
+            Some("ew-resize")
+        } else if self.hover {
+            Some("pointer")
+        } else {
+            None
+        }
+    }
+
+    fn clear_hover(&mut self) {
+        self.hover = false;
+        self.start_control.hover = false;
+        self.end_control.hover = false;
+    }
+
     fn selected(&self) -> bool {
         self.selected
     }
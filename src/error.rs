@@ -0,0 +1,99 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+
+#[derive(Debug)]
+pub enum FyError {
+    /// 没有找到指定id的canvas
+    CanvasNotFound(String),
+    /// 指定id的元素不是canvas
+    NotACanvas(String),
+    /// 没有找到指定id的元素
+    ElementNotFound(String),
+    /// 指定id的元素类型不对
+    UnexpectedElement { id: String, expected: &'static str },
+    /// 获取不到 2d context
+    ContextUnavailable,
+    /// 背景图片读取或者解码失败
+    ImageDecodeFailed(String),
+    /// 没有选择文件
+    EmptyFileSelection,
+    /// 场景 json 格式错误
+    InvalidSceneJson(String),
+    /// 没有找到指定id的控件
+    ComponentNotFound(u32),
+    /// 控件已经锁定，不能修改
+    ComponentLocked(u32),
+    /// 控件 id 或者外部 id 重复
+    DuplicateId(String),
+    /// 控件 id 已经分配完
+    IdExhausted,
+    /// 没有注册的样式类
+    StyleClassNotFound(String),
+    /// 不满足校验规则
+    RuleViolated(String),
+    /// js 传入的参数格式错误
+    InvalidArgument(String),
+    /// 其他 js 异常
+    Js(String),
+}
+
+impl fmt::Display for FyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FyError::CanvasNotFound(id) => write!(f, "canvas not found: {}", id),
+            FyError::NotACanvas(id) => write!(f, "element is not a canvas: {}", id),
+            FyError::ElementNotFound(id) => write!(f, "element not found: {}", id),
+            FyError::UnexpectedElement { id, expected } => {
+                write!(f, "element {} is not {}", id, expected)
+            }
+            FyError::ContextUnavailable => write!(f, "canvas 2d context unavailable"),
+            FyError::ImageDecodeFailed(msg) => write!(f, "image decode failed: {}", msg),
+            FyError::EmptyFileSelection => write!(f, "no file selected"),
+            FyError::InvalidSceneJson(msg) => write!(f, "invalid scene json: {}", msg),
+            FyError::ComponentNotFound(id) => write!(f, "component not found: {}", id),
+            FyError::ComponentLocked(id) => write!(f, "component is locked: {}", id),
+            FyError::DuplicateId(id) => write!(f, "duplicate component id: {}", id),
+            FyError::IdExhausted => write!(f, "no component id left to allocate"),
+            FyError::StyleClassNotFound(name) => write!(f, "style class not found: {}", name),
+            FyError::RuleViolated(msg) => write!(f, "validation rule violated: {}", msg),
+            FyError::InvalidArgument(msg) => write!(f, "invalid argument: {}", msg),
+            FyError::Js(msg) => write!(f, "js error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for FyError {}
+
+impl From<JsValue> for FyError {
+    fn from(value: JsValue) -> Self {
+        FyError::Js(format!("{:?}", value))
+    }
+}
+
+impl From<FyError> for JsValue {
+    fn from(err: FyError) -> Self {
+        JsError::new(&err.to_string()).into()
+    }
+}
+
+/// 事件回调里产生的错误，交给 js 设置的 on_error 回调
+/// 没有设置回调时输出到 console
+#[derive(Clone, Default)]
+pub struct ErrorHandler(Rc<RefCell<Option<js_sys::Function>>>);
+
+impl ErrorHandler {
+    pub fn set(&self, callback: Option<js_sys::Function>) {
+        *self.0.borrow_mut() = callback;
+    }
+
+    pub fn report(&self, err: FyError) {
+        match self.0.borrow().as_ref() {
+            Some(callback) => {
+                let _ = callback.call1(&JsValue::NULL, &JsValue::from(err));
+            }
+            None => log_error!("--> error: {}", err),
+        }
+    }
+}
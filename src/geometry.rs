@@ -0,0 +1,242 @@
+use serde::Serialize;
+
+use crate::component::Point;
+use crate::BgImgInfo;
+
+/// 控件的几何形状，坐标是 canvas 坐标
+#[derive(Debug, Copy, Clone)]
+pub enum Shape {
+    // 左上角和宽高
+    Rect { x: f64, y: f64, width: f64, height: f64 },
+    Line { start: Point, end: Point },
+    Circle { center: Point, radius: f64 },
+}
+
+impl Shape {
+    /// 形状类型的名字，js 里按这个名字配置规则
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Shape::Rect { .. } => "rect",
+            Shape::Line { .. } => "line",
+            Shape::Circle { .. } => "circle",
+        }
+    }
+
+    /// 顶点，矩形按顺时针，圆没有顶点
+    pub fn vertices(&self) -> Vec<(f64, f64)> {
+        match *self {
+            Shape::Rect { x, y, width, height } => vec![
+                (x, y),
+                (x + width, y),
+                (x + width, y + height),
+                (x, y + height),
+            ],
+            Shape::Line { start, end } => vec![
+                (start.x as f64, start.y as f64),
+                (end.x as f64, end.y as f64),
+            ],
+            Shape::Circle { .. } => Vec::new(),
+        }
+    }
+
+    /// 外接矩形 (x, y, width, height)
+    pub fn bounds(&self, units: &Units) -> (f64, f64, f64, f64) {
+        let (x, y, width, height) = match *self {
+            Shape::Rect { x, y, width, height } => (x, y, width, height),
+            Shape::Line { start, end } => {
+                let (x1, y1) = (start.x.min(end.x) as f64, start.y.min(end.y) as f64);
+                let (x2, y2) = (start.x.max(end.x) as f64, start.y.max(end.y) as f64);
+                (x1, y1, x2 - x1, y2 - y1)
+            }
+            Shape::Circle { center, radius } => (
+                center.x as f64 - radius,
+                center.y as f64 - radius,
+                radius * 2_f64,
+                radius * 2_f64,
+            ),
+        };
+        let (x, y) = units.point(x, y);
+        let (width, height) = units.size(width, height);
+        (x, y, width, height)
+    }
+
+    /// 是否是封闭的区域
+    pub fn closed(&self) -> bool {
+        !matches!(self, Shape::Line { .. })
+    }
+
+    /// 面积，线是 0
+    pub fn area(&self, units: &Units) -> f64 {
+        match *self {
+            Shape::Rect { width, height, .. } => {
+                let (width, height) = units.size(width, height);
+                width * height
+            }
+            Shape::Line { .. } => 0_f64,
+            Shape::Circle { radius, .. } => {
+                // x、y 缩放不同时是椭圆
+                let (a, b) = units.size(radius, radius);
+                std::f64::consts::PI * a * b
+            }
+        }
+    }
+
+    /// 周长，线是长度
+    pub fn perimeter(&self, units: &Units) -> f64 {
+        match *self {
+            Shape::Rect { width, height, .. } => {
+                let (width, height) = units.size(width, height);
+                2_f64 * (width + height)
+            }
+            Shape::Line { start, end } => {
+                let (dx, dy) = units.size((end.x - start.x) as f64, (end.y - start.y) as f64);
+                dx.hypot(dy)
+            }
+            Shape::Circle { radius, .. } => {
+                // 椭圆周长用 Ramanujan 近似公式，a == b 时就是圆的周长
+                let (a, b) = units.size(radius, radius);
+                let h = ((a - b) / (a + b)).powi(2);
+                if !h.is_finite() {
+                    return 0_f64;
+                }
+                std::f64::consts::PI * (a + b) * (1_f64 + 3_f64 * h / (10_f64 + (4_f64 - 3_f64 * h).sqrt()))
+            }
+        }
+    }
+
+    /// 形心
+    pub fn centroid(&self, units: &Units) -> (f64, f64) {
+        let (x, y) = match *self {
+            Shape::Rect { x, y, width, height } => (x + width / 2_f64, y + height / 2_f64),
+            Shape::Line { start, end } => (
+                (start.x + end.x) as f64 / 2_f64,
+                (start.y + end.y) as f64 / 2_f64,
+            ),
+            Shape::Circle { center, .. } => (center.x as f64, center.y as f64),
+        };
+        units.point(x, y)
+    }
+}
+
+/// 计算用的单位，canvas 像素或者原图像素
+#[derive(Debug, Copy, Clone)]
+pub struct Units {
+    scale_x: f64,
+    scale_y: f64,
+    dx: f64,
+    dy: f64,
+}
+
+impl Units {
+    pub const CANVAS: Units = Units {
+        scale_x: 1_f64,
+        scale_y: 1_f64,
+        dx: 0_f64,
+        dy: 0_f64,
+    };
+
+    /// 原图像素，和 BgImgInfo::to_image 一致
+    pub fn image(bg_info: &BgImgInfo) -> Units {
+        Units {
+            scale_x: bg_info.scale_x,
+            scale_y: bg_info.scale_y,
+            dx: bg_info.dx,
+            dy: bg_info.dy,
+        }
+    }
+
+    /// canvas 坐标转换成这个单位的坐标
+    pub fn point(&self, x: f64, y: f64) -> (f64, f64) {
+        ((x - self.dx) / self.scale_x, (y - self.dy) / self.scale_y)
+    }
+
+    /// 这个单位的坐标转换成 canvas 坐标
+    pub fn canvas_point(&self, x: f64, y: f64) -> (f64, f64) {
+        (x * self.scale_x + self.dx, y * self.scale_y + self.dy)
+    }
+
+    pub fn size(&self, width: f64, height: f64) -> (f64, f64) {
+        (width / self.scale_x, height / self.scale_y)
+    }
+}
+
+/// 控件的几何数据，返回给 js
+#[derive(Debug, Clone, Serialize)]
+pub struct Measurement {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub area: f64,
+    // 线是长度
+    pub perimeter: f64,
+    pub centroid_x: f64,
+    pub centroid_y: f64,
+}
+
+// 线段 ab 和 cd 是否相交，不算端点重合
+fn segments_cross(a: (f64, f64), b: (f64, f64), c: (f64, f64), d: (f64, f64)) -> bool {
+    let cross = |o: (f64, f64), p: (f64, f64), q: (f64, f64)| {
+        (p.0 - o.0) * (q.1 - o.1) - (p.1 - o.1) * (q.0 - o.0)
+    };
+    let d1 = cross(c, d, a);
+    let d2 = cross(c, d, b);
+    let d3 = cross(a, b, c);
+    let d4 = cross(a, b, d);
+    d1 * d2 < 0_f64 && d3 * d4 < 0_f64
+}
+
+/// 封闭多边形的边是否自相交，只比较不相邻的边
+pub fn self_intersecting(vertices: &[(f64, f64)]) -> bool {
+    let count = vertices.len();
+    if count < 4 {
+        return false;
+    }
+
+    let edge = |i: usize| (vertices[i], vertices[(i + 1) % count]);
+    for i in 0..count {
+        for j in (i + 2)..count {
+            // 第一条边和最后一条边相邻
+            if i == 0 && j == count - 1 {
+                continue;
+            }
+            let (a, b) = edge(i);
+            let (c, d) = edge(j);
+            if segments_cross(a, b, c, d) {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segments_cross_each_other() {
+        assert!(segments_cross((0.0, 0.0), (10.0, 10.0), (0.0, 10.0), (10.0, 0.0)));
+        // 平行、不相交、端点重合都不算
+        assert!(!segments_cross((0.0, 0.0), (10.0, 0.0), (0.0, 5.0), (10.0, 5.0)));
+        assert!(!segments_cross((0.0, 0.0), (4.0, 4.0), (6.0, 0.0), (10.0, -4.0)));
+        assert!(!segments_cross((0.0, 0.0), (10.0, 0.0), (10.0, 0.0), (10.0, 10.0)));
+    }
+
+    #[test]
+    fn polygon_self_intersecting() {
+        let square = [(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)];
+        assert!(!self_intersecting(&square));
+
+        // 两个对角交换之后是领结形
+        let bowtie = [(0.0, 0.0), (10.0, 10.0), (10.0, 0.0), (0.0, 10.0)];
+        assert!(self_intersecting(&bowtie));
+
+        // 凹多边形不算自相交
+        let concave = [(0.0, 0.0), (10.0, 0.0), (5.0, 5.0), (10.0, 10.0), (0.0, 10.0)];
+        assert!(!self_intersecting(&concave));
+
+        let triangle = [(0.0, 0.0), (10.0, 0.0), (5.0, 5.0)];
+        assert!(!self_intersecting(&triangle));
+    }
+}
@@ -0,0 +1,296 @@
+use serde::Serialize;
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::JsValue;
+
+use crate::error::{ErrorHandler, FyError};
+use crate::json;
+use crate::scene::ComponentData;
+use crate::ComponentVec;
+
+/// 对控件的一次修改，用于 undo/redo 和通知 js
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Change {
+    Rename {
+        id: u32,
+        old_title: String,
+        new_title: String,
+    },
+    // 值为 null 表示没有这个属性
+    Metadata {
+        id: u32,
+        key: String,
+        old_value: Option<serde_json::Value>,
+        new_value: Option<serde_json::Value>,
+    },
+    Add {
+        component: ComponentData,
+    },
+    Remove {
+        component: ComponentData,
+    },
+}
+
+impl Change {
+    /// 反向的修改，undo 时使用
+    pub fn reversed(&self) -> Change {
+        match self {
+            Change::Rename {
+                id,
+                old_title,
+                new_title,
+            } => Change::Rename {
+                id: *id,
+                old_title: new_title.clone(),
+                new_title: old_title.clone(),
+            },
+            Change::Metadata {
+                id,
+                key,
+                old_value,
+                new_value,
+            } => Change::Metadata {
+                id: *id,
+                key: key.clone(),
+                old_value: new_value.clone(),
+                new_value: old_value.clone(),
+            },
+            Change::Add { component } => Change::Remove {
+                component: component.clone(),
+            },
+            Change::Remove { component } => Change::Add {
+                component: component.clone(),
+            },
+        }
+    }
+
+    /// 锁定的控件不能修改 title 和删除，undo/redo 也一样
+    pub fn apply(&self, childs: &mut ComponentVec) -> Result<(), FyError> {
+        match self {
+            Change::Rename { id, new_title, .. } => {
+                let component = childs.get_mut(id).ok_or(FyError::ComponentNotFound(*id))?;
+                if component.flags().locked {
+                    return Err(FyError::ComponentLocked(*id));
+                }
+                component.set_title(new_title.clone());
+            }
+            Change::Metadata {
+                id, key, new_value, ..
+            } => {
+                let component = childs.get_mut(id).ok_or(FyError::ComponentNotFound(*id))?;
+                match new_value {
+                    Some(value) => component.metadata_mut().insert(key.clone(), value.clone()),
+                    None => component.metadata_mut().remove(key),
+                };
+            }
+            Change::Add { component } => {
+                if childs.contains_key(&component.id) {
+                    return Err(FyError::DuplicateId(component.id.to_string()));
+                }
+                let built = component.build()?;
+                childs.insert(component.id, built);
+            }
+            Change::Remove { component } => {
+                let locked = childs
+                    .get(&component.id)
+                    .map(|component| component.flags().locked)
+                    .ok_or(FyError::ComponentNotFound(component.id))?;
+                if locked {
+                    return Err(FyError::ComponentLocked(component.id));
+                }
+                childs.remove(&component.id);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 修改记录，支持 undo/redo
+#[derive(Default)]
+pub struct History {
+    undo_list: Vec<Change>,
+    redo_list: Vec<Change>,
+}
+
+impl History {
+    /// 记录已经生效的修改，新的修改会清掉 redo
+    pub fn record(&mut self, change: Change) {
+        self.undo_list.push(change);
+        self.redo_list.clear();
+    }
+
+    pub fn clear(&mut self) {
+        self.undo_list.clear();
+        self.redo_list.clear();
+    }
+
+    /// 撤销最近的修改，返回实际执行的修改
+    /// 修改失败时 (例如控件已经锁定) 记录保持不变
+    pub fn undo(&mut self, childs: &mut ComponentVec) -> Result<Option<Change>, FyError> {
+        let reversed = match self.undo_list.last() {
+            Some(change) => change.reversed(),
+            None => return Ok(None),
+        };
+
+        reversed.apply(childs)?;
+        if let Some(change) = self.undo_list.pop() {
+            self.redo_list.push(change);
+        }
+        Ok(Some(reversed))
+    }
+
+    /// 重做最近撤销的修改，返回实际执行的修改
+    /// 修改失败时记录保持不变
+    pub fn redo(&mut self, childs: &mut ComponentVec) -> Result<Option<Change>, FyError> {
+        let change = match self.redo_list.last() {
+            Some(change) => change.clone(),
+            None => return Ok(None),
+        };
+
+        change.apply(childs)?;
+        self.redo_list.pop();
+        self.undo_list.push(change.clone());
+        Ok(Some(change))
+    }
+}
+
+/// 修改事件，通过 js 设置的 on_change 回调通知
+/// 调用时不能持有 childs 的借用，js 回调里可能再调用 FyCanvas
+#[derive(Clone, Default)]
+pub struct ChangeHandler(Rc<RefCell<Option<js_sys::Function>>>);
+
+impl ChangeHandler {
+    pub fn set(&self, callback: Option<js_sys::Function>) {
+        *self.0.borrow_mut() = callback;
+    }
+
+    pub fn emit(&self, change: &Change, on_error: &ErrorHandler) {
+        let callback = match self.0.borrow().as_ref() {
+            Some(callback) => callback.clone(),
+            None => return,
+        };
+
+        match json::to_js(change) {
+            Ok(value) => {
+                let _ = callback.call1(&JsValue::NULL, &value);
+            }
+            Err(err) => on_error.report(err),
+        }
+    }
+}
+
+/// 执行修改: 修改控件，记录 history，通知 js
+#[derive(Clone)]
+pub struct ChangeRecorder {
+    pub childs: Rc<RefCell<ComponentVec>>,
+    pub history: Rc<RefCell<History>>,
+    pub on_change: ChangeHandler,
+    pub on_error: ErrorHandler,
+}
+
+impl ChangeRecorder {
+    pub fn new(childs: Rc<RefCell<ComponentVec>>, on_error: ErrorHandler) -> Self {
+        Self {
+            childs,
+            history: Rc::new(RefCell::new(History::default())),
+            on_change: ChangeHandler::default(),
+            on_error,
+        }
+    }
+
+    pub fn commit(&self, change: Change) -> Result<(), FyError> {
+        change.apply(&mut self.childs.borrow_mut())?;
+        self.history.borrow_mut().record(change.clone());
+        self.on_change.emit(&change, &self.on_error);
+        Ok(())
+    }
+
+    /// 没有可以撤销的修改时返回 false
+    pub fn undo(&self) -> Result<bool, FyError> {
+        let change = self
+            .history
+            .borrow_mut()
+            .undo(&mut self.childs.borrow_mut())?;
+        Ok(self.emit_applied(change))
+    }
+
+    /// 没有可以重做的修改时返回 false
+    pub fn redo(&self) -> Result<bool, FyError> {
+        let change = self
+            .history
+            .borrow_mut()
+            .redo(&mut self.childs.borrow_mut())?;
+        Ok(self.emit_applied(change))
+    }
+
+    fn emit_applied(&self, change: Option<Change>) -> bool {
+        match change {
+            Some(change) => {
+                self.on_change.emit(&change, &self.on_error);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::{ComponentStyle, Point, RectComponent};
+    use std::collections::HashMap;
+
+    fn childs() -> ComponentVec {
+        let style = ComponentStyle::default();
+        let rect = RectComponent::new(1, Point { x: 0, y: 0 }, Point { x: 10, y: 10 }, &style, "a".to_string());
+        let mut childs: ComponentVec = HashMap::new();
+        childs.insert(1, Box::new(rect));
+        childs
+    }
+
+    fn lock(childs: &mut ComponentVec, id: u32) {
+        let component = childs.get_mut(&id).unwrap();
+        let mut flags = component.flags();
+        flags.locked = true;
+        component.set_flags(flags);
+    }
+
+    #[test]
+    fn undo_rename_of_locked() {
+        let mut childs = childs();
+        let mut history = History::default();
+        let change = Change::Rename {
+            id: 1,
+            old_title: "a".to_string(),
+            new_title: "b".to_string(),
+        };
+        change.apply(&mut childs).unwrap();
+        history.record(change);
+
+        lock(&mut childs, 1);
+        assert!(matches!(history.undo(&mut childs), Err(FyError::ComponentLocked(1))));
+        assert_eq!(childs[&1].title(), "b");
+        // 记录保持不变，解锁之后还可以撤销
+        assert_eq!(history.undo_list.len(), 1);
+        assert!(history.redo_list.is_empty());
+    }
+
+    #[test]
+    fn redo_remove_of_locked() {
+        let mut childs = childs();
+        let mut history = History::default();
+        let change = Change::Remove {
+            component: ComponentData::from_component(childs[&1].as_ref()),
+        };
+        change.apply(&mut childs).unwrap();
+        history.record(change);
+
+        assert!(history.undo(&mut childs).unwrap().is_some());
+        lock(&mut childs, 1);
+        assert!(matches!(history.redo(&mut childs), Err(FyError::ComponentLocked(1))));
+        assert!(childs.contains_key(&1));
+        assert_eq!(history.redo_list.len(), 1);
+        assert!(history.undo_list.is_empty());
+    }
+}
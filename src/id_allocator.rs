@@ -0,0 +1,48 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use crate::error::FyError;
+
+/// 控件 id 分配器，id 只增不减
+/// 删除的 id 不会再分配，undo 恢复的控件不会和新的控件冲突
+#[derive(Clone, Default)]
+pub struct IdAllocator(Rc<Cell<u32>>);
+
+impl IdAllocator {
+    /// 导入过 u32::MAX 的 id 之后不能再分配，0 表示自动分配，也不能用
+    pub fn alloc(&self) -> Result<u32, FyError> {
+        let id = self.0.get().checked_add(1).ok_or(FyError::IdExhausted)?;
+        self.0.set(id);
+        Ok(id)
+    }
+
+    /// 导入时指定的 id，之后分配的 id 从它后面开始
+    pub fn reserve(&self, id: u32) {
+        if id > self.0.get() {
+            self.0.set(id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alloc_after_reserve() {
+        let id_allocator = IdAllocator::default();
+        assert_eq!(id_allocator.alloc().unwrap(), 1);
+        id_allocator.reserve(10);
+        id_allocator.reserve(5);
+        assert_eq!(id_allocator.alloc().unwrap(), 11);
+    }
+
+    #[test]
+    fn alloc_exhausted() {
+        let id_allocator = IdAllocator::default();
+        id_allocator.reserve(u32::MAX);
+        assert!(matches!(id_allocator.alloc(), Err(FyError::IdExhausted)));
+        // 失败之后不会回到 0
+        assert!(matches!(id_allocator.alloc(), Err(FyError::IdExhausted)));
+    }
+}
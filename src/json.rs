@@ -0,0 +1,21 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::error::FyError;
+
+/// js 对象 转换成 rust 结构，经过 JSON 字符串中转
+pub fn from_js<T: DeserializeOwned>(value: &JsValue) -> Result<T, FyError> {
+    // undefined、函数转换的结果不是字符串
+    let text = js_sys::JSON::stringify(value)
+        .map_err(|err| FyError::InvalidArgument(format!("{:?}", err)))?
+        .as_string()
+        .ok_or_else(|| FyError::InvalidArgument(format!("not a json value: {:?}", value)))?;
+    serde_json::from_str(&text).map_err(|err| FyError::InvalidArgument(err.to_string()))
+}
+
+/// rust 结构 转换成 js 对象
+pub fn to_js<T: Serialize>(value: &T) -> Result<JsValue, FyError> {
+    let text = serde_json::to_string(value).map_err(|err| FyError::Js(err.to_string()))?;
+    Ok(js_sys::JSON::parse(&text)?)
+}
@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+use web_sys::CanvasRenderingContext2d;
+
+use crate::component::Component;
+
+/// title 相对控件的位置
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LabelAnchor {
+    // 外接矩形的左上角，title 在角的上方
+    TopLeft,
+    // 控件中心
+    Center,
+    // 线的中点 / 矩形上边的中点 / 圆的顶点，title 在它的上方
+    AboveMidpoint,
+}
+
+/// 矩形区域 (x, y, width, height)
+pub type LabelRect = (f64, f64, f64, f64);
+
+/// 从 font 里取字号，例如 "16px serif"，取不到时按 16px
+fn font_size(font: &str) -> f64 {
+    font.split_whitespace()
+        .find_map(|part| part.strip_suffix("px"))
+        .and_then(|size| size.parse::<f64>().ok())
+        .unwrap_or(16_f64)
+}
+
+/// 计算 title 的背景框，文字宽度用 measure_text 测量，并限制在 visible 区域内
+pub fn title_box(
+    context: &CanvasRenderingContext2d,
+    component: &dyn Component,
+    visible: LabelRect,
+) -> Option<LabelRect> {
+    let title = component.title();
+    if title.is_empty() {
+        return None;
+    }
+    let style = component.style();
+
+    context.save();
+    context.set_font(style.font.as_str());
+    let text_width = context
+        .measure_text(title)
+        .map(|metrics| metrics.width())
+        .unwrap_or(0_f64);
+    context.restore();
+
+    let padding = style.label_padding;
+    let width = text_width + padding * 2_f64;
+    let height = font_size(&style.font) + padding * 2_f64;
+
+    let (x, y) = component.title_position();
+    let (x, y) = match style.label_anchor {
+        LabelAnchor::TopLeft => (x, y - height),
+        LabelAnchor::Center => (x - width / 2_f64, y - height / 2_f64),
+        LabelAnchor::AboveMidpoint => (x - width / 2_f64, y - height - padding),
+    };
+
+    // 不超出可见的图片区域，区域比文字还小时保证左上角可见
+    let (v_x, v_y, v_width, v_height) = visible;
+    let x = x.min(v_x + v_width - width).max(v_x);
+    let y = y.min(v_y + v_height - height).max(v_y);
+
+    Some((x, y, width, height))
+}
+
+/// 画控件的 title，带背景框
+pub fn paint_title(context: &CanvasRenderingContext2d, component: &dyn Component, visible: LabelRect) {
+    let (x, y, width, height) = match title_box(context, component, visible) {
+        Some(title_box) => title_box,
+        None => return,
+    };
+    let style = component.style();
+
+    context.save();
+    if !style.label_background.is_empty() {
+        context.set_fill_style_str(style.label_background.as_str());
+        context.fill_rect(x, y, width, height);
+    }
+
+    let label_color = if style.label_color.is_empty() {
+        style.line_color.as_str()
+    } else {
+        style.label_color.as_str()
+    };
+    context.set_fill_style_str(label_color);
+    context.set_font(style.font.as_str());
+    context.set_text_baseline("middle");
+    context.set_text_align("left");
+    let _ = context.fill_text(
+        component.title(),
+        x + style.label_padding,
+        y + height / 2_f64,
+    );
+    context.restore();
+}
@@ -1,6 +1,22 @@
+#[macro_use]
+mod logger;
+
 mod component;
+mod error;
+mod geometry;
+mod history;
+mod id_allocator;
+mod json;
+mod label;
+mod listener;
 mod render;
+mod theme;
+mod title_editor;
+mod repaint;
+mod scene;
+mod snap;
 mod utils;
+mod validate;
 
 use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
@@ -8,7 +24,20 @@ use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 
 use component::*;
+use geometry::Units;
+use error::ErrorHandler;
+use history::{Change, ChangeRecorder};
+use id_allocator::IdAllocator;
+pub use error::FyError;
+pub use logger::LogLevel;
+pub use theme::Theme;
+use theme::Styles;
+use listener::{Listener, Listeners};
 use render::*;
+use repaint::Repaint;
+use scene::{ComponentData, SceneData};
+use title_editor::TitleEditor;
+use validate::{ValidationIssue, ValidationRules};
 use std::rc::Rc;
 
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
@@ -18,18 +47,44 @@ use std::rc::Rc;
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
 type ComponentVec = HashMap<u32, Box<dyn Component>>;
+// 粘贴、复制控件时的偏移
+const PASTE_OFFSET: i32 = 10;
+
+type FrameClosure = Rc<RefCell<Option<Closure<dyn FnMut()>>>>;
 
 #[wasm_bindgen]
 extern "C" {
     fn alert(s: &str);
+}
 
-    #[wasm_bindgen(js_namespace = console)]
-    fn log(s: &str);
+/// 背景图片的缩放方式
+#[wasm_bindgen]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BgFitMode {
+    /// 完整显示图片，保持比例，留黑边
+    Contain,
+    /// 填满canvas，保持比例，超出部分裁剪
+    Cover,
+    /// 拉伸填满canvas，不保持比例
+    Stretch,
+    /// 按原始像素大小显示
+    ActualSize,
+}
+
+/// 背景图片的对齐方式
+#[wasm_bindgen]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BgAlign {
+    TopLeft,
+    Center,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct BgImgInfo {
-    scale: f64,
+    fit: BgFitMode,
+    align: BgAlign,
+    scale_x: f64,
+    scale_y: f64,
     dx: f64,
     dy: f64,
     width: f64,
@@ -39,32 +94,111 @@ pub struct BgImgInfo {
 }
 
 impl BgImgInfo {
-    fn new(c_width: u32, c_height: u32, img_width: u32, img_height: u32) -> BgImgInfo {
+    fn new(
+        c_width: u32,
+        c_height: u32,
+        img_width: u32,
+        img_height: u32,
+        fit: BgFitMode,
+        align: BgAlign,
+    ) -> BgImgInfo {
         let c_width = c_width as f64;
         let c_height = c_height as f64;
         let img_width = img_width as f64;
         let img_height = img_height as f64;
 
-        let c_ratio = c_width / c_height;
-        let i_ratio = img_width / img_height;
-        let scale = if i_ratio > c_ratio {
-            c_width / img_width
-        } else {
-            c_height / img_height
+        let (scale_x, scale_y) = match fit {
+            BgFitMode::Contain => {
+                let scale = (c_width / img_width).min(c_height / img_height);
+                (scale, scale)
+            }
+            BgFitMode::Cover => {
+                let scale = (c_width / img_width).max(c_height / img_height);
+                (scale, scale)
+            }
+            BgFitMode::Stretch => (c_width / img_width, c_height / img_height),
+            BgFitMode::ActualSize => (1_f64, 1_f64),
+        };
+
+        let width = img_width * scale_x;
+        let height = img_height * scale_y;
+
+        // cover 模式下 dx/dy 可能为负数，超出canvas的部分被裁剪
+        let (dx, dy) = match align {
+            BgAlign::TopLeft => (0_f64, 0_f64),
+            BgAlign::Center => ((c_width - width) / 2_f64, (c_height - height) / 2_f64),
         };
 
-        let dx = (c_width - img_width * scale) / 2_f64;
-        let dy = (c_height - img_height * scale) / 2_f64;
         BgImgInfo {
+            fit,
+            align,
+            scale_x,
+            scale_y,
             dx,
             dy,
-            scale,
-            width: img_width * scale,
-            height: img_height * scale,
+            width,
+            height,
             origin_width: img_width,
             origin_height: img_height,
         }
     }
+
+    /// canvas坐标 转换成 原图坐标
+    pub fn to_image(&self, x: f64, y: f64) -> (f64, f64) {
+        ((x - self.dx) / self.scale_x, (y - self.dy) / self.scale_y)
+    }
+
+    /// 原图坐标 转换成 canvas坐标
+    pub fn to_canvas(&self, x: f64, y: f64) -> (f64, f64) {
+        (x * self.scale_x + self.dx, y * self.scale_y + self.dy)
+    }
+}
+
+type ImagePoint = (f64, f64);
+
+/// 控件在原图上的坐标，切换缩放方式时用来换算
+/// canvas 坐标是整数，每次换算都取整会累积误差，所以控件没有修改过时使用上次的原图坐标
+#[derive(Default)]
+struct ImagePoints {
+    // 上次换算之后的背景
+    bg_info: Option<BgImgInfo>,
+    // 控件 id -> (换算之后的 canvas 坐标, 原图坐标)
+    points: HashMap<u32, (Vec<Point>, Vec<ImagePoint>)>,
+}
+
+impl ImagePoints {
+    /// 控件从 old_info 换算到 new_info，保持在图片上的位置不变
+    fn remap(&mut self, childs: &mut ComponentVec, old_info: &BgImgInfo, new_info: &BgImgInfo) {
+        // 中间换过图片，之前的原图坐标不能用了
+        if self.bg_info != Some(*old_info) {
+            self.points.clear();
+        }
+
+        for (id, component) in childs.iter_mut() {
+            let points = component.control_points();
+            let image_points = match self.points.get(id) {
+                Some((canvas_points, image_points)) if *canvas_points == points => image_points.clone(),
+                _ => points
+                    .iter()
+                    .map(|point| old_info.to_image(point.x as f64, point.y as f64))
+                    .collect(),
+            };
+
+            let mapped: Vec<Point> = image_points
+                .iter()
+                .map(|(img_x, img_y)| {
+                    let (x, y) = new_info.to_canvas(*img_x, *img_y);
+                    Point {
+                        x: x.round() as i32,
+                        y: y.round() as i32,
+                    }
+                })
+                .collect();
+            component.set_control_points(&mapped);
+            self.points.insert(*id, (component.control_points(), image_points));
+        }
+        self.bg_info = Some(*new_info);
+    }
 }
 
 #[wasm_bindgen]
@@ -76,8 +210,27 @@ pub struct FyCanvas {
     canvas: web_sys::HtmlCanvasElement,
 
     render: Rc<RefCell<FyRender>>,
+    repaint: Rc<Repaint>,
     bg_img: Rc<Cell<Option<BgImgInfo>>>,
+    bg_fit: Rc<Cell<(BgFitMode, BgAlign)>>,
     childs: Rc<RefCell<ComponentVec>>,
+    styles: Rc<RefCell<Styles>>,
+
+    // 视频背景的逐帧重画，video_frame 是还没执行的 animation frame
+    video_loop: FrameClosure,
+    video_frame: Rc<Cell<Option<i32>>>,
+
+    listeners: RefCell<Listeners>,
+    on_error: ErrorHandler,
+
+    // 修改记录和修改事件
+    changes: ChangeRecorder,
+    title_editor: RefCell<Option<Rc<TitleEditor>>>,
+
+    // 复制之后粘贴的次数，每次粘贴多偏移一些，避免和之前的重叠
+    paste_count: Rc<Cell<i32>>,
+    id_allocator: IdAllocator,
+    image_points: RefCell<ImagePoints>,
 }
 
 #[wasm_bindgen]
@@ -87,34 +240,38 @@ impl FyCanvas {
 
         let canvas = document
             .get_element_by_id(id)
-            .ok_or_else(||JsError::new("body not find"))?
-            .dyn_into::<web_sys::HtmlCanvasElement>()?;
+            .ok_or_else(|| FyError::CanvasNotFound(id.to_string()))?
+            .dyn_into::<web_sys::HtmlCanvasElement>()
+            .map_err(|_| FyError::NotACanvas(id.to_string()))?;
 
         let canvas_height = canvas.height();
         let canvas_width = canvas.width();
 
-        let canvas_context = canvas
-            .get_context("2d")?
-            .ok_or_else(||JsError::new("document not find"))?
-            .dyn_into::<web_sys::CanvasRenderingContext2d>()?;
+        let canvas_context = context_2d(&canvas)?;
 
         let cache_canvas = document
             .create_element("canvas")?
             .dyn_into::<web_sys::HtmlCanvasElement>()?;
-        cache_canvas.set_width(canvas_height);
-        cache_canvas.set_height(canvas_width);
+        cache_canvas.set_width(canvas_width);
+        cache_canvas.set_height(canvas_height);
 
-        let cache_context = cache_canvas
-            .get_context("2d")?
-            .ok_or_else(||JsError::new("document not find"))?
-            .dyn_into::<web_sys::CanvasRenderingContext2d>()?;
+        let cache_context = context_2d(&cache_canvas)?;
 
-        let render = FyRender::new(canvas_context, cache_canvas, cache_context);
+        // 背景信息 FyCanvas 和 render 共用一份
+        let bg_img = Rc::new(Cell::new(None));
+        let render = FyRender::new(canvas_context, cache_canvas, cache_context, bg_img.clone());
 
         let childs = Rc::new(RefCell::new(HashMap::new()));
 
+        // 新建控件使用的默认样式，和主题提供的样式类
+        let styles = Styles::new(Theme::Light);
+        let style_classes = &styles.classes;
+
+        let id_allocator = IdAllocator::default();
+
         // 加测试数据
-        let component = test_create_rect_component(1, 100, 100);
+        let mut component = test_create_rect_component(id_allocator.alloc()?, 100, 100, &style_classes[theme::CLASS_INTRUSION]);
+        component.set_style_class(Some(theme::CLASS_INTRUSION.to_string()));
         childs.borrow_mut().insert(component.id(), component);
 
         // let component = test_create_rect_component(1, 150, 300);
@@ -123,10 +280,19 @@ impl FyCanvas {
         // let component = test_create_line_component(3, 150, 300, 300, 200);
         // childs.borrow_mut().insert(component.id(), component);
 
-        let component = test_create_line_component(4, 210, 130, 110, 240);
+        let mut component = test_create_line_component(
+            id_allocator.alloc()?,
+            210,
+            130,
+            110,
+            240,
+            &style_classes[theme::CLASS_COUNTING_LINE],
+        );
+        component.set_style_class(Some(theme::CLASS_COUNTING_LINE.to_string()));
         childs.borrow_mut().insert(component.id(), component);
 
-        let component = test_create_circle_component(5,210, 130,100);
+        let mut component = test_create_circle_component(id_allocator.alloc()?, 210, 130, 100, &style_classes[theme::CLASS_EXCLUSION]);
+        component.set_style_class(Some(theme::CLASS_EXCLUSION.to_string()));
         childs.borrow_mut().insert(component.id(), component);
 
 
@@ -134,96 +300,993 @@ impl FyCanvas {
         //     .borrow_mut()
         //     .push(test_create_circle_component(6,230, 180, 50));
 
+        let on_error = ErrorHandler::default();
+        let changes = ChangeRecorder::new(childs.clone(), on_error.clone());
+        let render = Rc::new(RefCell::new(render));
+        let repaint = Repaint::new(render.clone(), childs.clone(), on_error.clone());
+
         Ok(FyCanvas {
             id: id.to_string(),
             height: canvas_height,
             width: canvas_width,
             canvas,
-            render: Rc::new(RefCell::new(render)),
-            bg_img: Rc::new(Cell::new(None)),
+            render,
+            repaint,
+            bg_img,
+            bg_fit: Rc::new(Cell::new((BgFitMode::Contain, BgAlign::Center))),
             childs,
+            styles: Rc::new(RefCell::new(styles)),
+            video_loop: Rc::new(RefCell::new(None)),
+            video_frame: Rc::new(Cell::new(None)),
+            listeners: RefCell::new(Listeners::default()),
+            on_error,
+            changes,
+            title_editor: RefCell::new(None),
+            paste_count: Rc::new(Cell::new(0)),
+            id_allocator,
+            image_points: RefCell::new(ImagePoints::default()),
         })
     }
 
-    pub fn bind_bg_input(&self, input_id: &str) -> Result<(), JsValue> {
+    /// 修改控件样式，partial 只需要包含要修改的字段
+    /// 控件不再引用样式类，以当前样式为基础修改，之后样式类或者主题变化时不会覆盖这次的修改
+    pub fn set_style(&self, id: u32, partial: JsValue) -> Result<(), JsValue> {
+        let patch: ComponentStylePatch = json::from_js(&partial)?;
+
+        let mut component_list = self.childs.borrow_mut();
+        let component = component_list
+            .get_mut(&id)
+            .ok_or(FyError::ComponentNotFound(id))?;
+
+        let mut style = component.style();
+        style.apply(patch);
+        component.set_style(style);
+        component.set_style_class(None);
+        drop(component_list);
+
+        self.repaint.request();
+        Ok(())
+    }
+
+    pub fn get_style(&self, id: u32) -> Result<JsValue, JsValue> {
+        let component_list = self.childs.borrow();
+        let component = component_list
+            .get(&id)
+            .ok_or(FyError::ComponentNotFound(id))?;
+        Ok(json::to_js(&component.style())?)
+    }
+
+    /// 修改新建控件使用的默认样式，已有控件不受影响
+    /// 粘贴、导入没有样式也没有样式类的控件时使用
+    pub fn set_default_style(&self, partial: JsValue) -> Result<(), JsValue> {
+        let patch: ComponentStylePatch = json::from_js(&partial)?;
+        self.styles.borrow_mut().default_style.apply(patch);
+        Ok(())
+    }
+
+    pub fn get_default_style(&self) -> Result<JsValue, JsValue> {
+        Ok(json::to_js(&self.styles.borrow().default_style)?)
+    }
+
+    /// 修改 title，记录到 history 并触发 on_change，锁定的控件报错
+    pub fn set_title(&self, id: u32, title: &str) -> Result<(), JsValue> {
+        rename_component(&self.changes, id, title.to_string())?;
+        self.repaint.request();
+        Ok(())
+    }
+
+    pub fn get_title(&self, id: u32) -> Result<String, JsValue> {
+        let component_list = self.childs.borrow();
+        let component = component_list
+            .get(&id)
+            .ok_or(FyError::ComponentNotFound(id))?;
+        Ok(component.title().to_string())
+    }
+
+    /// 注册或者修改样式类，使用这个样式类的控件全部更新
+    /// 新的样式类以默认样式为基础
+    pub fn register_style_class(&self, name: &str, partial: JsValue) -> Result<(), JsValue> {
+        let patch: ComponentStylePatch = json::from_js(&partial)?;
+
+        let mut styles = self.styles.borrow_mut();
+        let mut style = styles
+            .classes
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| styles.default_style.clone());
+        style.apply(patch);
+        styles.classes.insert(name.to_string(), style);
+        drop(styles);
+
+        self.restyle_class(name);
+        self.repaint.request();
+        Ok(())
+    }
+
+    pub fn get_style_class(&self, name: &str) -> Result<JsValue, JsValue> {
+        let styles = self.styles.borrow();
+        let style = styles
+            .classes
+            .get(name)
+            .ok_or_else(|| FyError::StyleClassNotFound(name.to_string()))?;
+        Ok(json::to_js(style)?)
+    }
+
+    /// 控件引用样式类，传 null 取消引用，保留当前样式
+    pub fn set_style_class(&self, id: u32, name: Option<String>) -> Result<(), JsValue> {
+        let style = match &name {
+            Some(name) => Some(
+                self.styles
+                    .borrow()
+                    .classes
+                    .get(name)
+                    .cloned()
+                    .ok_or_else(|| FyError::StyleClassNotFound(name.clone()))?,
+            ),
+            None => None,
+        };
+
+        let mut component_list = self.childs.borrow_mut();
+        let component = component_list
+            .get_mut(&id)
+            .ok_or(FyError::ComponentNotFound(id))?;
+        component.set_style_class(name);
+        if let Some(style) = style {
+            component.set_style(style);
+        }
+        drop(component_list);
+
+        self.repaint.request();
+        Ok(())
+    }
+
+    /// 切换内置主题，替换默认样式和内置的样式类，自定义的样式类保持不变
+    pub fn apply_theme(&self, theme: Theme) {
+        let mut styles = self.styles.borrow_mut();
+        styles.default_style = theme::default_style(theme);
+
+        let classes = theme::style_classes(theme);
+        let names: Vec<String> = classes.keys().cloned().collect();
+        styles.classes.extend(classes);
+        drop(styles);
+
+        for name in names.iter() {
+            self.restyle_class(name);
+        }
+        self.repaint.request();
+    }
+
+    /// 打开或关闭拖拽吸附
+    pub fn set_snap_enabled(&self, enabled: bool) {
+        self.render.borrow_mut().snap.enabled = enabled;
+    }
+
+    pub fn snap_enabled(&self) -> bool {
+        self.render.borrow().snap.enabled
+    }
+
+    /// 设置吸附距离，单位是 canvas 像素
+    pub fn set_snap_threshold(&self, threshold: f64) {
+        self.render.borrow_mut().snap.threshold = threshold.max(0_f64);
+    }
+
+    /// 设置吸附网格大小，0 表示不吸附网格
+    pub fn set_snap_grid(&self, grid: u32) {
+        self.render.borrow_mut().snap.grid = grid;
+    }
+
+    /// 设置吸附目标: 其他控件的控制点和中点、其他控件的边、图片边框
+    pub fn set_snap_targets(&self, points: bool, edges: bool, border: bool) {
+        let mut render = self.render.borrow_mut();
+        render.snap.to_points = points;
+        render.snap.to_edges = edges;
+        render.snap.to_border = border;
+    }
+
+    /// 拖拽时是否把控制点限制在图片区域内，不影响已有的控件
+    pub fn set_clamp_to_image(&self, clamp: bool) {
+        self.render.borrow_mut().clamp_to_image = clamp;
+    }
+
+    /// 返回超出图片区域的控件 id
+    pub fn out_of_bounds(&self) -> Vec<u32> {
+        let ids = self.render.borrow().out_of_bounds(&self.childs);
+        if !ids.is_empty() {
+            log_warn!("components out of image bounds: {:?}", ids);
+        }
+        ids
+    }
+
+    /// 控件的几何数据 {x, y, width, height, area, perimeter, centroid_x, centroid_y}
+    /// image_units 为 true 时按原图像素计算，需要已经设置了背景
+    pub fn get_geometry(&self, id: u32, image_units: bool) -> Result<JsValue, JsValue> {
+        let units = self.units(image_units)?;
+        let component_list = self.childs.borrow();
+        let component = component_list
+            .get(&id)
+            .ok_or(FyError::ComponentNotFound(id))?;
+        Ok(json::to_js(&component.measure(&units))?)
+    }
+
+    /// 点是否在控件上，image_units 为 true 时 (x, y) 是原图坐标
+    pub fn contains_point(&self, id: u32, x: f64, y: f64, image_units: bool) -> Result<bool, JsValue> {
+        let units = self.units(image_units)?;
+        let component_list = self.childs.borrow();
+        let component = component_list
+            .get(&id)
+            .ok_or(FyError::ComponentNotFound(id))?;
+        Ok(component.contains_point(x, y, &units))
+    }
+
+    /// 设置校验规则，rules 的字段都是可选的，不设置的规则不检查
+    /// 尺寸单位是原图像素，没有背景时是 canvas 像素
+    pub fn set_validation_rules(&self, rules: JsValue) -> Result<(), JsValue> {
+        let rules: ValidationRules = json::from_js(&rules)?;
+        self.render.borrow_mut().rules = rules;
+        Ok(())
+    }
+
+    pub fn get_validation_rules(&self) -> Result<JsValue, JsValue> {
+        Ok(json::to_js(&self.render.borrow().rules)?)
+    }
+
+    /// 按校验规则检查所有控件，返回问题列表 [{id, rule, message}]
+    /// 有背景时也检查控件是否超出图片区域
+    pub fn validate(&self) -> Result<JsValue, JsValue> {
+        let render = self.render.borrow();
+        let mut issues = render.rules.check_all(&self.childs.borrow(), &render.units());
+        for id in render.out_of_bounds(&self.childs) {
+            issues.push(ValidationIssue::component(
+                id,
+                "out_of_bounds",
+                "outside the image".to_string(),
+            ));
+        }
+        Ok(json::to_js(&issues)?)
+    }
+
+    /// 设置日志级别，全局生效
+    pub fn set_log_level(&self, level: LogLevel) {
+        logger::set_level(level);
+    }
+
+    /// 设置日志回调 hook(level, message)，日志转给 js 处理，传 null 恢复输出到 console
+    pub fn set_log_hook(&self, hook: Option<js_sys::Function>) {
+        logger::set_hook(hook);
+    }
+
+    /// 设置修改事件回调 on_change(change)，change.type 是修改类型
+    pub fn set_on_change(&self, callback: Option<js_sys::Function>) {
+        self.changes.on_change.set(callback);
+    }
+
+    /// 撤销最近的修改，没有可以撤销的返回 false
+    pub fn undo(&self) -> Result<bool, JsValue> {
+        let done = self.changes.undo();
+        self.render.borrow_mut().forget_removed(&self.childs);
+        self.repaint.request();
+        Ok(done?)
+    }
+
+    /// 重做最近撤销的修改，没有可以重做的返回 false
+    pub fn redo(&self) -> Result<bool, JsValue> {
+        let done = self.changes.redo();
+        self.render.borrow_mut().forget_removed(&self.childs);
+        self.repaint.request();
+        Ok(done?)
+    }
+
+    /// 当前选中的控件
+    pub fn selected_id(&self) -> Option<u32> {
+        self.render.borrow().focus_id
+    }
+
+    /// 选中控件，传 null 取消选中
+    pub fn select(&self, id: Option<u32>) -> Result<(), JsValue> {
+        if let Some(id) = id {
+            if !self.childs.borrow().contains_key(&id) {
+                return Err(FyError::ComponentNotFound(id).into());
+            }
+        }
+        self.render.borrow_mut().set_focus(&self.childs, id);
+        self.repaint.request();
+        Ok(())
+    }
+
+    /// 复制选中的控件，返回剪贴板的 json，没有选中时返回 null
+    pub fn copy(&self) -> Result<Option<String>, JsValue> {
+        let id = match self.selected_id() {
+            Some(id) => id,
+            None => return Ok(None),
+        };
+        let text = copy_component(&self.childs, id)?;
+        self.paste_count.set(0);
+        Ok(Some(text))
+    }
+
+    /// 剪切选中的控件，返回剪贴板的 json，没有选中时返回 null
+    pub fn cut(&self) -> Result<Option<String>, JsValue> {
+        let text = match self.copy()? {
+            Some(text) => text,
+            None => return Ok(None),
+        };
+        if let Some(id) = self.selected_id() {
+            remove_component(&self.changes, id)?;
+            self.render.borrow_mut().set_focus(&self.childs, None);
+        }
+        self.repaint.request();
+        Ok(Some(text))
+    }
+
+    /// 粘贴 copy/cut 返回的 json，控件使用新的 id 并偏移一点，返回新的 id
+    pub fn paste(&self, text: &str) -> Result<Vec<u32>, JsValue> {
+        let components = scene::from_clipboard(text)
+            .ok_or_else(|| FyError::InvalidSceneJson("not a component clipboard".to_string()))?;
+        let ids = paste(
+            &self.changes,
+            &self.render,
+            &self.id_allocator,
+            &self.styles,
+            &self.paste_count,
+            components,
+        )?;
+        self.repaint.request();
+        Ok(ids)
+    }
+
+    /// 复制一个控件，返回新的 id
+    pub fn duplicate(&self, id: u32) -> Result<u32, JsValue> {
+        let data = self
+            .childs
+            .borrow()
+            .get(&id)
+            .map(|component| ComponentData::from_component(component.as_ref()))
+            .ok_or(FyError::ComponentNotFound(id))?;
+
+        let rules = self.render.borrow().rules.clone();
+        let ids = paste_components(
+            &self.changes,
+            &self.id_allocator,
+            &self.styles,
+            &rules,
+            vec![data],
+            PASTE_OFFSET,
+        )?;
+        self.render.borrow_mut().set_focus(&self.childs, ids.last().copied());
+        self.repaint.request();
+        Ok(ids[0])
+    }
+
+    /// 设置控件的自定义属性，value 是任意可以转成 json 的值
+    /// 记录到 history 并触发 on_change
+    pub fn set_metadata(&self, id: u32, key: &str, value: JsValue) -> Result<(), JsValue> {
+        let value: serde_json::Value = json::from_js(&value)?;
+        self.update_metadata(id, key, Some(value))
+    }
+
+    /// 删除控件的自定义属性
+    pub fn remove_metadata(&self, id: u32, key: &str) -> Result<(), JsValue> {
+        self.update_metadata(id, key, None)
+    }
+
+    /// 读取控件的自定义属性，没有时返回 undefined
+    pub fn get_metadata(&self, id: u32, key: &str) -> Result<JsValue, JsValue> {
+        let component_list = self.childs.borrow();
+        let component = component_list
+            .get(&id)
+            .ok_or(FyError::ComponentNotFound(id))?;
+        match component.metadata().get(key) {
+            Some(value) => Ok(json::to_js(value)?),
+            None => Ok(JsValue::UNDEFINED),
+        }
+    }
+
+    /// 读取控件的所有自定义属性，返回一个对象
+    pub fn get_all_metadata(&self, id: u32) -> Result<JsValue, JsValue> {
+        let component_list = self.childs.borrow();
+        let component = component_list
+            .get(&id)
+            .ok_or(FyError::ComponentNotFound(id))?;
+        Ok(json::to_js(component.metadata())?)
+    }
+
+    /// 锁定控件: 仍然显示，可以选中和复制，不能拖拽、修改 title 和剪切
+    /// 自定义属性是业务数据，锁定之后仍然可以修改
+    pub fn set_locked(&self, id: u32, locked: bool) -> Result<(), JsValue> {
+        self.update_flags(id, |flags| flags.locked = locked)
+    }
+
+    /// 显示或者隐藏控件，隐藏的控件不响应鼠标
+    pub fn set_visible(&self, id: u32, visible: bool) -> Result<(), JsValue> {
+        self.update_flags(id, |flags| flags.visible = visible)
+    }
+
+    /// 设置控件是否响应鼠标，不能选中的控件仍然显示
+    pub fn set_selectable(&self, id: u32, selectable: bool) -> Result<(), JsValue> {
+        self.update_flags(id, |flags| flags.selectable = selectable)
+    }
+
+    /// 控件的状态 {locked, visible, selectable}
+    pub fn get_flags(&self, id: u32) -> Result<JsValue, JsValue> {
+        let component_list = self.childs.borrow();
+        let component = component_list
+            .get(&id)
+            .ok_or(FyError::ComponentNotFound(id))?;
+        Ok(json::to_js(&component.flags())?)
+    }
+
+    /// 设置控件的外部 id，例如数据库记录的 uuid，和其他控件重复时报错
+    pub fn set_external_id(&self, id: u32, external_id: Option<String>) -> Result<(), JsValue> {
+        if let Some(external_id) = &external_id {
+            if let Some(other) = self.find_by_external_id(external_id) {
+                if other != id {
+                    return Err(FyError::DuplicateId(external_id.clone()).into());
+                }
+            }
+        }
+
+        let mut component_list = self.childs.borrow_mut();
+        let component = component_list
+            .get_mut(&id)
+            .ok_or(FyError::ComponentNotFound(id))?;
+        component.set_external_id(external_id);
+        Ok(())
+    }
+
+    pub fn get_external_id(&self, id: u32) -> Result<Option<String>, JsValue> {
+        let component_list = self.childs.borrow();
+        let component = component_list
+            .get(&id)
+            .ok_or(FyError::ComponentNotFound(id))?;
+        Ok(component.external_id().map(|external_id| external_id.to_string()))
+    }
+
+    /// 按外部 id 查找控件
+    pub fn find_by_external_id(&self, external_id: &str) -> Option<u32> {
+        self.childs
+            .borrow()
+            .iter()
+            .find(|(_id, component)| component.external_id() == Some(external_id))
+            .map(|(id, _component)| *id)
+    }
+
+    /// 导出所有控件的 json，按 id 排序
+    pub fn export_scene(&self) -> Result<String, JsValue> {
+        let mut components: Vec<ComponentData> = self
+            .childs
+            .borrow()
+            .values()
+            .map(|component| ComponentData::from_component(component.as_ref()))
+            .collect();
+        components.sort_by_key(|component| component.id);
+
+        let scene = SceneData { components };
+        Ok(serde_json::to_string(&scene).map_err(|err| FyError::Js(err.to_string()))?)
+    }
+
+    /// 导入 export_scene 导出的 json，替换现有的所有控件，清空修改记录
+    /// 没有 style 的控件使用样式类或者默认样式
+    /// id 或者 external_id 重复时报错，现有的控件保持不变；id 为 0 的控件自动分配 id
+    /// 返回导入后控件的 id，顺序和 json 里一致
+    pub fn import_scene(&self, text: &str) -> Result<Vec<u32>, JsValue> {
+        let mut scene: SceneData = serde_json::from_str(text)
+            .map_err(|err| FyError::InvalidSceneJson(err.to_string()))?;
+        scene.check_ids()?;
+
+        for component in scene.components.iter() {
+            self.id_allocator.reserve(component.id);
+        }
+        for component in scene.components.iter_mut().filter(|component| component.id == 0) {
+            component.id = self.id_allocator.alloc()?;
+        }
+        for component in scene.components.iter_mut() {
+            self.styles.borrow().fill(component);
+        }
+
+        // 先全部创建，有错误时不修改现有的控件
+        let mut ids = Vec::new();
+        let mut component_list: ComponentVec = HashMap::new();
+        for data in scene.components.iter() {
+            ids.push(data.id);
+            component_list.insert(data.id, data.build()?);
+        }
+
+        *self.childs.borrow_mut() = component_list;
+        self.changes.history.borrow_mut().clear();
+        self.render.borrow_mut().set_focus(&self.childs, None);
+        self.repaint.request();
+        Ok(ids)
+    }
+
+    /// 监听 document 的 copy/cut/paste，和系统剪贴板交换控件的 json
+    /// 只在 canvas 有键盘焦点时处理，其他时候保留浏览器默认的行为，例如复制页面上选中的文字
+    pub fn bind_clipboard_event(&self) -> Result<(), JsValue> {
+        let mut listeners = Vec::new();
         let document = document();
-        let input = document
-            .get_element_by_id(input_id)
-            .ok_or_else(||JsError::new("input not find"))?
-            .dyn_into::<web_sys::HtmlInputElement>()?;
 
+        // canvas 默认不能获得焦点，设置 tabindex 之后点击 canvas 时获得焦点
+        if !self.canvas.has_attribute("tabindex") {
+            self.canvas.set_tab_index(0);
+        }
+
+        for event_type in ["copy", "cut"] {
+            let canvas = self.canvas.clone();
+            let render = self.render.clone();
+            let changes = self.changes.clone();
+            let repaint = self.repaint.clone();
+            let paste_count = self.paste_count.clone();
+            let closure = Closure::wrap(Box::new(move |event: web_sys::ClipboardEvent| {
+                if !canvas_focused(&canvas) {
+                    return;
+                }
+                let (id, data) = match (render.borrow().focus_id, event.clipboard_data()) {
+                    (Some(id), Some(data)) => (id, data),
+                    _ => return,
+                };
+
+                let result = copy_component(&changes.childs, id)
+                    .and_then(|text| Ok(data.set_data("text/plain", &text)?))
+                    .and_then(|_| {
+                        if event_type == "cut" {
+                            remove_component(&changes, id)?;
+                            render.borrow_mut().set_focus(&changes.childs, None);
+                            repaint.request();
+                        }
+                        Ok(())
+                    });
+                match result {
+                    Ok(()) => {
+                        paste_count.set(0);
+                        event.prevent_default();
+                    }
+                    Err(err) => changes.on_error.report(err),
+                }
+            }) as Box<dyn FnMut(_)>);
+            listeners.push(Listener::add(&document, event_type, closure)?);
+        }
+
+        let canvas = self.canvas.clone();
+        let render = self.render.clone();
+        let changes = self.changes.clone();
+        let repaint = self.repaint.clone();
+        let id_allocator = self.id_allocator.clone();
+        let styles = self.styles.clone();
+        let paste_count = self.paste_count.clone();
+        let closure_paste = Closure::wrap(Box::new(move |event: web_sys::ClipboardEvent| {
+            if !canvas_focused(&canvas) {
+                return;
+            }
+            let text = match event.clipboard_data().and_then(|data| data.get_data("text/plain").ok()) {
+                Some(text) => text,
+                None => return,
+            };
+            // 不是控件数据，交给页面的其他处理
+            let components = match scene::from_clipboard(&text) {
+                Some(components) => components,
+                None => return,
+            };
+
+            event.prevent_default();
+            if let Err(err) = paste(&changes, &render, &id_allocator, &styles, &paste_count, components) {
+                changes.on_error.report(err);
+            }
+            repaint.request();
+        }) as Box<dyn FnMut(_)>);
+        listeners.push(Listener::add(&document, "paste", closure_paste)?);
+
+        self.listeners.borrow_mut().replace("clipboard", listeners);
+        Ok(())
+    }
+
+    /// 设置错误回调，事件处理中的错误通过回调通知 js，而不是 panic
+    pub fn set_on_error(&self, callback: Option<js_sys::Function>) {
+        self.on_error.set(callback);
+    }
+
+    pub fn bind_bg_input(&self, input_id: &str) -> Result<(), JsValue> {
+        let input = get_element::<web_sys::HtmlInputElement>(input_id, "input")?;
+
+        let (file_reader, mut listeners) = self.create_bg_reader()?;
+        let on_error = self.on_error.clone();
+
+        // input change 回调
+        let closure_input = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            log_debug!("--> closure_input, type: {:?}", event.type_());
+            let ret = event_target::<web_sys::HtmlInputElement>(&event).and_then(|ele_input| {
+                let file = ele_input
+                    .files()
+                    .and_then(|files| files.get(0))
+                    .ok_or(FyError::EmptyFileSelection)?;
+                read_image_file(&file_reader, &file)
+            });
+            if let Err(err) = ret {
+                on_error.report(err);
+            }
+        }) as Box<dyn FnMut(_)>);
+
+        listeners.push(Listener::add(&input, "change", closure_input)?);
+        self.listeners.borrow_mut().replace("bg_input", listeners);
+
+        self.bind_mouse_event()?;
+
+        Ok(())
+    }
+
+    /// 从文件管理器拖放图片到canvas上，作为背景
+    pub fn bind_bg_drop(&self) -> Result<(), JsValue> {
+        let (file_reader, mut listeners) = self.create_bg_reader()?;
+
+        // dragover 需要 prevent_default，否则不会触发 drop
+        let closure_dragover = Closure::wrap(Box::new(move |event: web_sys::DragEvent| {
+            event.prevent_default();
+            if let Some(data_transfer) = event.data_transfer() {
+                data_transfer.set_drop_effect("copy");
+            }
+        }) as Box<dyn FnMut(_)>);
+
+        listeners.push(Listener::add(&self.canvas, "dragover", closure_dragover)?);
+
+        // drop 回调
+        let on_error = self.on_error.clone();
+        let closure_drop = Closure::wrap(Box::new(move |event: web_sys::DragEvent| {
+            event.prevent_default();
+            log_debug!("--> closure_drop, type: {:?}", event.type_());
+
+            let ret = event
+                .data_transfer()
+                .and_then(|data_transfer| data_transfer.files())
+                .and_then(|files| files.get(0))
+                .ok_or(FyError::EmptyFileSelection)
+                .and_then(|file| read_image_file(&file_reader, &file));
+            if let Err(err) = ret {
+                on_error.report(err);
+            }
+        }) as Box<dyn FnMut(_)>);
+
+        listeners.push(Listener::add(&self.canvas, "drop", closure_drop)?);
+        self.listeners.borrow_mut().replace("bg_drop", listeners);
+
+        Ok(())
+    }
+
+    /// 使用 video 作为背景，播放时每一帧重画背景，暂停时定格在当前帧，只重画控件
+    pub fn bind_bg_video(&self, video_id: &str) -> Result<(), JsValue> {
+        let video = get_element::<web_sys::HtmlVideoElement>(video_id, "video")?;
+
+        self.init_video_loop(&video);
+        let mut listeners = Vec::new();
+
+        let width = self.width;
+        let height = self.height;
+
+        let bg = self.bg_img.clone();
+        let bg_fit = self.bg_fit.clone();
+        let render = self.render.clone();
+        let repaint = self.repaint.clone();
+        let on_error = self.on_error.clone();
+
+        // video loadedmetadata 回调，视频尺寸确定后计算背景信息
+        let closure_meta = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            log_debug!("--> closure_meta, type: {:?}", event.type_());
+            let ele_video = match event_target::<web_sys::HtmlVideoElement>(&event) {
+                Ok(ele_video) => ele_video,
+                Err(err) => return on_error.report(err),
+            };
+
+            let (fit, align) = bg_fit.get();
+            let bg_info = BgImgInfo::new(
+                width,
+                height,
+                ele_video.video_width(),
+                ele_video.video_height(),
+                fit,
+                align,
+            );
+            log_info!("--> closure_meta, bg_info: {:?}", bg_info);
+
+            bg.set(Some(bg_info));
+            if let Err(err) = render.borrow_mut().update_bg_video(&ele_video) {
+                on_error.report(err);
+            }
+            repaint.request();
+        }) as Box<dyn FnMut(_)>);
+        listeners.push(Listener::add(&video, "loadedmetadata", closure_meta)?);
+
+        // video play 回调，启动逐帧重画
+        let video_loop = self.video_loop.clone();
+        let video_frame = self.video_frame.clone();
+        let closure_play = Closure::wrap(Box::new(move |_event: web_sys::Event| {
+            if video_frame.get().is_none() {
+                if let Some(video_loop) = video_loop.borrow().as_ref() {
+                    video_frame.set(Some(request_animation_frame(video_loop)));
+                }
+            }
+        }) as Box<dyn FnMut(_)>);
+        listeners.push(Listener::add(&video, "play", closure_play)?);
+
+        // video pause/seeked 回调，把定格帧画到 cache 上
+        for event_type in ["pause", "seeked"] {
+            let render = self.render.clone();
+            let repaint = self.repaint.clone();
+            let on_error = self.on_error.clone();
+            let closure_frame = Closure::wrap(Box::new(move |_event: web_sys::Event| {
+                if let Err(err) = render.borrow().draw_bg() {
+                    on_error.report(err);
+                }
+                repaint.request();
+            }) as Box<dyn FnMut(_)>);
+            listeners.push(Listener::add(&video, event_type, closure_frame)?);
+        }
+        self.listeners.borrow_mut().replace("bg_video", listeners);
+
+        // 绑定之前视频可能已经加载或者已经在播放
+        if video.ready_state() >= web_sys::HtmlMediaElement::HAVE_METADATA {
+            video.dispatch_event(&web_sys::Event::new("loadedmetadata")?)?;
+        }
+        if !video.paused() {
+            video.dispatch_event(&web_sys::Event::new("play")?)?;
+        }
+
+        self.bind_mouse_event()?;
+
+        Ok(())
+    }
+
+    /// 暂停视频背景，定格当前帧
+    pub fn pause_video(&self) -> Result<(), JsValue> {
+        match self.render.borrow().bg_video() {
+            Some(video) => video.pause()?,
+            None => log_warn!("--> pause_video, background is not a video"),
+        }
+        Ok(())
+    }
+
+    /// 继续播放视频背景
+    pub fn play_video(&self) -> Result<(), JsValue> {
+        match self.render.borrow().bg_video() {
+            Some(video) => {
+                let _ = video.play()?;
+            }
+            None => log_warn!("--> play_video, background is not a video"),
+        }
+        Ok(())
+    }
+
+    /// 设置背景图片的缩放和对齐方式，已有控件跟随图片重新映射坐标
+    pub fn set_bg_fit(&self, fit: BgFitMode, align: BgAlign) -> Result<(), JsValue> {
+        self.bg_fit.set((fit, align));
+
+        let old_info = match self.bg_img.get() {
+            Some(info) => info,
+            None => return Ok(()),
+        };
+        if old_info.fit == fit && old_info.align == align {
+            return Ok(());
+        }
+
+        let bg_info = BgImgInfo::new(
+            self.width,
+            self.height,
+            old_info.origin_width as u32,
+            old_info.origin_height as u32,
+            fit,
+            align,
+        );
+
+        // 控件保存的是canvas坐标，通过原图坐标中转，保持在图片上的位置不变
+        self.image_points
+            .borrow_mut()
+            .remap(&mut self.childs.borrow_mut(), &old_info, &bg_info);
+
+        self.bg_img.set(Some(bg_info));
+        self.render.borrow().draw_bg()?;
+        self.repaint.request();
+        Ok(())
+    }
+
+    /// 移除所有事件监听，取消还没执行的 animation frame
+    /// 单页应用切换路由时调用，之后可以重新 bind
+    pub fn destroy(&self) {
+        self.listeners.borrow_mut().clear();
+
+        self.repaint.cancel();
+        if let Some(id) = self.video_frame.take() {
+            let _ = window().cancel_animation_frame(id);
+        }
+        self.video_loop.borrow_mut().take();
+        self.title_editor.borrow_mut().take();
+    }
+}
+
+impl Drop for FyCanvas {
+    fn drop(&mut self) {
+        self.destroy();
+    }
+}
+
+impl FyCanvas {
+    /// 修改控件状态，不能再响应鼠标的控件取消选中和 hover
+    fn update_flags(&self, id: u32, f: impl FnOnce(&mut ComponentFlags)) -> Result<(), JsValue> {
+        let mut component_list = self.childs.borrow_mut();
+        let component = component_list
+            .get_mut(&id)
+            .ok_or(FyError::ComponentNotFound(id))?;
+
+        let mut flags = component.flags();
+        f(&mut flags);
+        component.set_flags(flags);
+        if !flags.hittable() {
+            component.clear_hover();
+        }
+        drop(component_list);
+
+        let mut render = self.render.borrow_mut();
+        if !flags.hittable() && render.focus_id == Some(id) {
+            render.set_focus(&self.childs, None);
+        }
+        if !flags.hittable() && render.hover.map(|(hover_id, _cursor)| hover_id) == Some(id) {
+            render.hover = None;
+        }
+        drop(render);
+
+        self.repaint.request();
+        Ok(())
+    }
+
+    /// 修改自定义属性，value 为 None 时删除，值没有变化时什么都不做
+    fn update_metadata(&self, id: u32, key: &str, value: Option<serde_json::Value>) -> Result<(), JsValue> {
+        let old_value = self
+            .childs
+            .borrow()
+            .get(&id)
+            .map(|component| component.metadata().get(key).cloned())
+            .ok_or(FyError::ComponentNotFound(id))?;
+        if old_value == value {
+            return Ok(());
+        }
+
+        self.changes.commit(Change::Metadata {
+            id,
+            key: key.to_string(),
+            old_value,
+            new_value: value,
+        })?;
+        Ok(())
+    }
+
+    /// 几何计算用的单位，按原图像素时需要有背景
+    fn units(&self, image_units: bool) -> Result<Units, FyError> {
+        if !image_units {
+            return Ok(Units::CANVAS);
+        }
+        self.bg_img
+            .get()
+            .map(|bg_info| Units::image(&bg_info))
+            .ok_or_else(|| FyError::InvalidArgument("no background image".to_string()))
+    }
+
+    /// 使用这个样式类的控件，更新成样式类的样式
+    fn restyle_class(&self, name: &str) {
+        let styles = self.styles.borrow();
+        let style = match styles.classes.get(name) {
+            Some(style) => style,
+            None => return,
+        };
+
+        for component in self.childs.borrow_mut().values_mut() {
+            if component.style_class() == Some(name) {
+                component.set_style(style.clone());
+            }
+        }
+    }
+
+    /// 创建 FileReader -> HtmlImageElement 的解码链路，图片加载后更新背景并重画
+    fn create_bg_reader(&self) -> Result<(web_sys::FileReader, Vec<Listener>), JsValue> {
         let file_reader = web_sys::FileReader::new()?;
         let img = web_sys::HtmlImageElement::new()?;
+        let mut listeners = Vec::new();
 
         let width = self.width;
         let height = self.height;
 
         let bg = self.bg_img.clone();
+        let bg_fit = self.bg_fit.clone();
         let render = self.render.clone();
-        let childs = self.childs.clone();
+        let repaint = self.repaint.clone();
+        let on_error = self.on_error.clone();
 
         // img onload 回调
         let closure_image = Closure::wrap(Box::new(move |event: web_sys::Event| {
-            log(&format!("--> closure_image, type: {:?}", event.type_()));
-            let ele_image = event
-                .target()
-                .unwrap()
-                .dyn_into::<web_sys::HtmlImageElement>()
-                .unwrap();
+            log_debug!("--> closure_image, type: {:?}", event.type_());
+            let ele_image = match event_target::<web_sys::HtmlImageElement>(&event) {
+                Ok(ele_image) => ele_image,
+                Err(err) => return on_error.report(err),
+            };
             let img_width = ele_image.width();
             let img_height = ele_image.height();
-            log(&format!(
+            log_info!(
                 "--> closure_image, img, width: {:?}, height: {:?}",
                 img_width, img_height
-            ));
+            );
 
-            let bg_info = BgImgInfo::new(width, height, img_width, img_height);
-            log(&format!("--> closure_image, bg_info: {:?}", bg_info));
+            let (fit, align) = bg_fit.get();
+            let bg_info = BgImgInfo::new(width, height, img_width, img_height, fit, align);
+            log_info!("--> closure_image, bg_info: {:?}", bg_info);
 
             bg.set(Some(bg_info));
-            log("--> closure_image, draw bg on cache");
-            render.borrow().update_bg(&ele_image, &bg_info);
-            FyCanvas::repaint(render.clone(), childs.clone());
+            log_debug!("--> closure_image, draw bg on cache");
+            if let Err(err) = render.borrow_mut().update_bg(&ele_image) {
+                on_error.report(err);
+            }
+            repaint.request();
+        }) as Box<dyn FnMut(_)>);
+        listeners.push(Listener::add(&img, "load", closure_image)?);
+
+        // img onerror 回调，文件不是能解码的图片
+        let on_error = self.on_error.clone();
+        let closure_image_error = Closure::wrap(Box::new(move |_event: web_sys::Event| {
+            on_error.report(FyError::ImageDecodeFailed("unsupported image data".to_string()));
         }) as Box<dyn FnMut(_)>);
-        img.set_onload(Some(closure_image.as_ref().unchecked_ref()));
-        closure_image.forget();
+        listeners.push(Listener::add(&img, "error", closure_image_error)?);
 
         // filereader onload 回调
+        let on_error = self.on_error.clone();
         let closure_reader = Closure::wrap(Box::new(move |event: web_sys::Event| {
-            log(&format!("--> closure_reader, type: {:?}", event.type_()));
-            let ele_reader = event
-                .target()
-                .unwrap()
-                .dyn_into::<web_sys::FileReader>()
-                .unwrap();
-            img.set_src(ele_reader.result().unwrap().as_string().unwrap().as_str());
+            log_debug!("--> closure_reader, type: {:?}", event.type_());
+            let ret = event_target::<web_sys::FileReader>(&event).and_then(|ele_reader| {
+                ele_reader
+                    .result()?
+                    .as_string()
+                    .ok_or_else(|| FyError::ImageDecodeFailed("unexpected reader result".to_string()))
+            });
+            match ret {
+                Ok(data_url) => img.set_src(&data_url),
+                Err(err) => on_error.report(err),
+            }
         }) as Box<dyn FnMut(_)>);
-        file_reader.set_onload(Some(closure_reader.as_ref().unchecked_ref()));
-        closure_reader.forget();
+        listeners.push(Listener::add(&file_reader, "load", closure_reader)?);
 
-        // input change 回调
-        let closure_input = Closure::wrap(Box::new(move |event: web_sys::Event| {
-            log(&format!("--> closure_input, type: {:?}", event.type_()));
-            let ele_input = event
-                .target()
-                .unwrap()
-                .dyn_into::<web_sys::HtmlInputElement>()
-                .unwrap();
-            let file = ele_input.files().unwrap().get(0).unwrap();
-            file_reader.read_as_data_url(&file).unwrap();
-        }) as Box<dyn FnMut(_)>);
+        Ok((file_reader, listeners))
+    }
 
-        input.add_event_listener_with_callback("change", closure_input.as_ref().unchecked_ref())?;
-        closure_input.forget();
+    /// 视频播放时每一帧: 画当前帧到 cache，再画控件；暂停后停止循环
+    /// 是否在播放直接看 video，play 可能在 loadedmetadata 之前触发，那时背景还没有设置
+    fn init_video_loop(&self, video: &web_sys::HtmlVideoElement) {
+        // 重新绑定时替换旧的循环，先取消旧循环还没执行的 frame
+        if let Some(id) = self.video_frame.take() {
+            let _ = window().cancel_animation_frame(id);
+        }
 
-        self.bind_mouse_event();
+        let video = video.clone();
+        let video_loop = self.video_loop.clone();
+        let video_frame = self.video_frame.clone();
+        let render = self.render.clone();
+        let repaint = self.repaint.clone();
+        let on_error = self.on_error.clone();
 
-        Ok(())
+        let closure = Closure::wrap(Box::new(move || {
+            video_frame.set(None);
+
+            let playing = !video.paused() && !video.ended();
+
+            if let Err(err) = render.borrow().draw_bg() {
+                on_error.report(err);
+            }
+            repaint.paint_now();
+
+            if playing {
+                if let Some(video_loop) = video_loop.borrow().as_ref() {
+                    video_frame.set(Some(request_animation_frame(video_loop)));
+                }
+            }
+        }) as Box<dyn FnMut()>);
+
+        *self.video_loop.borrow_mut() = Some(closure);
     }
-}
 
-impl FyCanvas {
-    pub fn bind_mouse_event(&self) {
+    pub fn bind_mouse_event(&self) -> Result<(), JsValue> {
+        let mut listeners = Vec::new();
+
         let render = self.render.clone();
         let render2 = self.render.clone();
         let render3 = self.render.clone();
@@ -232,10 +1295,16 @@ impl FyCanvas {
         let childs2 = self.childs.clone();
         let childs3 = self.childs.clone();
 
+        let repaint = self.repaint.clone();
+        let repaint2 = self.repaint.clone();
+        let repaint3 = self.repaint.clone();
+
+        let canvas2 = self.canvas.clone();
+
         // 鼠标down
         let closure_down = Closure::wrap(Box::new(move |event: web_sys::MouseEvent| {
-            log(&format!("--> mouse down, type: {:?}", event));
-            log(&format!(
+            log_debug!("--> mouse down, type: {:?}", event);
+            log_debug!(
                 "--> screen:({},{}), client:({},{}), offset:({},{})",
                 event.screen_x(),
                 event.screen_y(),
@@ -243,21 +1312,18 @@ impl FyCanvas {
                 event.client_y(),
                 event.offset_x(),
                 event.offset_y(),
-            ));
+            );
 
             //
             render.borrow_mut().mouse_down(childs.clone(),event.offset_x(),
                                            event.offset_y(),);
 
             // 刷新ui
-            FyCanvas::repaint(render.clone(), childs.clone());
+            repaint.request();
 
         }) as Box<dyn FnMut(_)>);
 
-        self.canvas
-            .add_event_listener_with_callback("mousedown", closure_down.as_ref().unchecked_ref())
-            .unwrap();
-        closure_down.forget();
+        listeners.push(Listener::add(&self.canvas, "mousedown", closure_down)?);
 
 
         // 鼠标移动
@@ -276,25 +1342,45 @@ impl FyCanvas {
             if event.buttons() ==1 {
                 //
                 render2.borrow_mut().mouse_move(childs2.clone(),event.offset_x(),
-                                                event.offset_y(),);
+                                                event.offset_y(),
+                                                DragModifiers::from_event(&event),);
 
                 // 刷新ui
-                FyCanvas::repaint(render2.clone(), childs2.clone());
-                log("mouse move, repaint");
+                repaint2.request();
+                log_trace!("mouse move, repaint");
+            } else {
+                // 没有按下鼠标，更新 hover 和鼠标指针
+                let cursor = render2.borrow_mut().mouse_hover(childs2.clone(), event.offset_x(),
+                                                               event.offset_y(),);
+                if let Some(cursor) = cursor {
+                    let _ = canvas2.style().set_property("cursor", cursor);
+                    repaint2.request();
+                }
             }
 
 
         }) as Box<dyn FnMut(_)>);
 
-        self.canvas
-            .add_event_listener_with_callback("mousemove", closure_move.as_ref().unchecked_ref())
-            .unwrap();
-        closure_move.forget();
+        listeners.push(Listener::add(&self.canvas, "mousemove", closure_move)?);
+
+        // 鼠标离开 canvas
+        let render4 = self.render.clone();
+        let childs4 = self.childs.clone();
+        let repaint4 = self.repaint.clone();
+        let canvas4 = self.canvas.clone();
+        let closure_leave = Closure::wrap(Box::new(move |_event: web_sys::MouseEvent| {
+            if render4.borrow_mut().clear_hover(childs4.clone()) {
+                repaint4.request();
+            }
+            let _ = canvas4.style().set_property("cursor", "default");
+        }) as Box<dyn FnMut(_)>);
+
+        listeners.push(Listener::add(&self.canvas, "mouseleave", closure_leave)?);
 
         // 鼠标up
         let closure_up = Closure::wrap(Box::new(move |event: web_sys::MouseEvent| {
-            log(&format!("--> mouse up, type: {:?}", event));
-            log(&format!(
+            log_debug!("--> mouse up, type: {:?}", event);
+            log_debug!(
                 "--> screen:({},{}), client:({},{}), offset:({},{})",
                 event.screen_x(),
                 event.screen_y(),
@@ -302,150 +1388,284 @@ impl FyCanvas {
                 event.client_y(),
                 event.offset_x(),
                 event.offset_y(),
-            ));
+            );
 
             //
             render3.borrow_mut().mouse_up(childs3.clone(),event.offset_x(),
                                            event.offset_y(),);
 
             // 刷新ui
-            FyCanvas::repaint(render3.clone(), childs3.clone());
+            repaint3.request();
 
         }) as Box<dyn FnMut(_)>);
 
-        self.canvas
-            .add_event_listener_with_callback("mouseup", closure_up.as_ref().unchecked_ref())
-            .unwrap();
-        closure_up.forget();
+        listeners.push(Listener::add(&self.canvas, "mouseup", closure_up)?);
+
+        self.bind_title_edit(&mut listeners)?;
+        self.listeners.borrow_mut().replace("mouse", listeners);
+
+        Ok(())
     }
 
-    fn repaint(render: Rc<RefCell<FyRender>>, childs: Rc<RefCell<ComponentVec>>) {
-        let closure = Closure::wrap(Box::new(move || {
-            render.borrow().paint(childs.clone());
-        }) as Box<dyn FnMut()>);
+    /// 双击 title 或者控件，在 title 的位置打开输入框修改 title
+    /// Enter 或者失去焦点时提交，Escape 取消
+    fn bind_title_edit(&self, listeners: &mut Vec<Listener>) -> Result<(), JsValue> {
+        let editor = Rc::new(TitleEditor::new(&document())?);
+        *self.title_editor.borrow_mut() = Some(editor.clone());
 
-        request_animation_frame(&closure);
-        closure.forget();
+        // canvas 双击
+        let canvas = self.canvas.clone();
+        let render = self.render.clone();
+        let childs = self.childs.clone();
+        let editor_open = editor.clone();
+        let closure_dblclick = Closure::wrap(Box::new(move |event: web_sys::MouseEvent| {
+            let (x, y) = (event.offset_x() as f64, event.offset_y() as f64);
+            log_debug!("--> dblclick, offset:({},{})", x, y);
+
+            let render = render.borrow();
+            let id = match render.title_target(&childs, x, y) {
+                Some(id) => id,
+                None => return,
+            };
+
+            let component_list = childs.borrow();
+            let component = match component_list.get(&id) {
+                Some(component) => component,
+                None => return,
+            };
+
+            // 没有 title 时，输入框放在 title 的锚点上
+            let (t_x, t_y, t_width, _t_height) =
+                label::title_box(&render.canvas_ctx, component.as_ref(), render.visible_rect())
+                    .unwrap_or_else(|| {
+                        let (t_x, t_y) = component.title_position();
+                        (t_x, t_y, 0_f64, 0_f64)
+                    });
+
+            let rect = canvas.get_bounding_client_rect();
+            editor_open.open(
+                id,
+                component.title(),
+                rect.left() + t_x,
+                rect.top() + t_y,
+                t_width.max(120_f64),
+                &component.style().font,
+            );
+        }) as Box<dyn FnMut(_)>);
+        listeners.push(Listener::add(&self.canvas, "dblclick", closure_dblclick)?);
+
+        // 输入框按键
+        let changes = self.changes.clone();
+        let repaint = self.repaint.clone();
+        let editor_key = editor.clone();
+        let closure_keydown = Closure::wrap(Box::new(move |event: web_sys::KeyboardEvent| {
+            match event.key().as_str() {
+                "Enter" => {
+                    event.prevent_default();
+                    commit_title_edit(&editor_key, &changes, &repaint);
+                }
+                "Escape" => {
+                    event.prevent_default();
+                    editor_key.close();
+                }
+                _ => {}
+            }
+        }) as Box<dyn FnMut(_)>);
+        listeners.push(Listener::add(editor.input(), "keydown", closure_keydown)?);
+
+        // 输入框失去焦点
+        let changes = self.changes.clone();
+        let repaint = self.repaint.clone();
+        let editor_blur = editor.clone();
+        let closure_blur = Closure::wrap(Box::new(move |_event: web_sys::Event| {
+            commit_title_edit(&editor_blur, &changes, &repaint);
+        }) as Box<dyn FnMut(_)>);
+        listeners.push(Listener::add(editor.input(), "blur", closure_blur)?);
+
+        Ok(())
     }
 }
 
-//-----------------------------------------------------------------
-//-----------------------------------------------------------------
-
-fn test_create_rect_component(id: u32, x: i32, y: i32) -> Box<dyn Component> {
-    let width = 200;
-    let height = 100;
-    let control_width = 8;
-
-    let style = ComponentStyle {
-        font: "16px serif".to_string(),
-        line_width: 2,
-        line_color: "blue".to_string(),
-        line_focus_color: "red".to_string(),
-        control_line_width: 2,
-        control_width,
-        control_line_color: "blue".to_string(),
-        control_fill_color: "red".to_string(),
-    };
+/// 修改 title，记录到 history 并触发 on_change，title 没有变化时什么都不做
+/// 锁定的控件不能修改 title
+fn rename_component(changes: &ChangeRecorder, id: u32, new_title: String) -> Result<(), FyError> {
+    let (old_title, locked) = changes
+        .childs
+        .borrow()
+        .get(&id)
+        .map(|component| (component.title().to_string(), component.flags().locked))
+        .ok_or(FyError::ComponentNotFound(id))?;
+    if locked {
+        return Err(FyError::ComponentLocked(id));
+    }
+    if old_title == new_title {
+        return Ok(());
+    }
 
-    let comp = RectComponent {
+    changes.commit(Change::Rename {
         id,
-        style,
+        old_title,
+        new_title,
+    })
+}
 
-        width,
-        height,
+/// 控件转换成剪贴板的 json
+fn copy_component(childs: &Rc<RefCell<ComponentVec>>, id: u32) -> Result<String, FyError> {
+    let data = childs
+        .borrow()
+        .get(&id)
+        .map(|component| ComponentData::from_component(component.as_ref()))
+        .ok_or(FyError::ComponentNotFound(id))?;
+    scene::to_clipboard(vec![data])
+}
 
-        start_control: ControlPoint {
-            point: Point { x, y },
-            width: control_width,
-            selected: false
-        },
-        end_control: ControlPoint {
-            point: Point {
-                x: x + width as i32,
-                y: y + height as i32,
-            },
-            width: control_width,
-            selected: false
-        },
+/// 删除控件，记录到 history 并触发 on_change
+/// 锁定的控件不能删除
+fn remove_component(changes: &ChangeRecorder, id: u32) -> Result<(), FyError> {
+    let component = changes
+        .childs
+        .borrow()
+        .get(&id)
+        .map(|component| ComponentData::from_component(component.as_ref()))
+        .ok_or(FyError::ComponentNotFound(id))?;
+    if component.flags.locked {
+        return Err(FyError::ComponentLocked(id));
+    }
+    changes.commit(Change::Remove { component })
+}
 
-        selected: false,
-        title: "抓拍区域".to_string(),
-    };
-    Box::new(comp)
+/// 添加控件，使用新的 id 并偏移 offset，返回新的 id
+/// 复制出来的是新的记录，不保留外部 id 和锁定；没有样式的控件使用样式类或者默认样式
+/// 开启了 enforce_live 时，超出控件数量的规则报错，一个都不添加
+fn paste_components(
+    changes: &ChangeRecorder,
+    id_allocator: &IdAllocator,
+    styles: &RefCell<Styles>,
+    rules: &ValidationRules,
+    components: Vec<ComponentData>,
+    offset: i32,
+) -> Result<Vec<u32>, FyError> {
+    if rules.enforce_live {
+        let kinds = components.iter().map(|component| component.kind.as_str());
+        rules.check_add(&changes.childs.borrow(), kinds)?;
+    }
+
+    // 先分配所有的 id，分配不了时一个都不添加
+    let ids = components
+        .iter()
+        .map(|_component| id_allocator.alloc())
+        .collect::<Result<Vec<u32>, FyError>>()?;
+    for (mut component, id) in components.into_iter().zip(ids.iter()) {
+        styles.borrow().fill(&mut component);
+        component.id = *id;
+        component.external_id = None;
+        component.flags.locked = false;
+        component.translate(offset, offset);
+        changes.commit(Change::Add { component })?;
+    }
+    Ok(ids)
 }
 
-fn test_create_line_component(id: u32, x1: i32, y1: i32, x2: i32, y2: i32) -> Box<dyn Component> {
-    let control_width = 8;
-
-    let style = ComponentStyle {
-        font: "16px serif".to_string(),
-        line_width: 2,
-        line_color: "blue".to_string(),
-        line_focus_color: "red".to_string(),
-        control_line_width: 2,
-        control_width: 8,
-        control_line_color: "blue".to_string(),
-        control_fill_color: "red".to_string(),
+/// 粘贴剪贴板里的控件，选中最后一个
+fn paste(
+    changes: &ChangeRecorder,
+    render: &Rc<RefCell<FyRender>>,
+    id_allocator: &IdAllocator,
+    styles: &RefCell<Styles>,
+    paste_count: &Cell<i32>,
+    components: Vec<ComponentData>,
+) -> Result<Vec<u32>, FyError> {
+    // js 的 on_change 回调里可能再调用 FyCanvas，不能一直持有 render 的借用
+    let rules = render.borrow().rules.clone();
+    let ids = paste_components(
+        changes,
+        id_allocator,
+        styles,
+        &rules,
+        components,
+        PASTE_OFFSET * (paste_count.get() + 1),
+    )?;
+    paste_count.set(paste_count.get() + 1);
+    render.borrow_mut().set_focus(&changes.childs, ids.last().copied());
+    Ok(ids)
+}
+
+/// canvas 有键盘焦点，剪贴板事件才处理控件
+/// 编辑 title 时焦点在输入框里，也不处理
+fn canvas_focused(canvas: &web_sys::HtmlCanvasElement) -> bool {
+    document()
+        .active_element()
+        .is_some_and(|element| canvas.is_same_node(Some(&element)))
+}
+
+/// 提交输入框里的 title
+fn commit_title_edit(editor: &TitleEditor, changes: &ChangeRecorder, repaint: &Repaint) {
+    let id = match editor.close() {
+        Some(id) => id,
+        None => return,
     };
 
-    let comp = LineComponent {
-        id,
-        style,
-        title: "边界线".to_string(),
+    if let Err(err) = rename_component(changes, id, editor.value()) {
+        changes.on_error.report(err);
+    }
+    repaint.request();
+}
 
-        start_control: ControlPoint {
-            point: Point { x: x1, y: y1 },
-            width: control_width,
-            selected: false
-        },
-        end_control: ControlPoint {
-            point: Point { x: x2, y: y2 },
-            width: control_width,
-            selected: false
-        },
+//-----------------------------------------------------------------
+//-----------------------------------------------------------------
 
-        selected: false,
-    };
+fn test_create_rect_component(id: u32, x: i32, y: i32, style: &ComponentStyle) -> Box<dyn Component> {
+    let width = 200;
+    let height = 100;
+
+    let comp = RectComponent::new(
+        id,
+        Point { x, y },
+        Point {
+            x: x + width,
+            y: y + height,
+        },
+        style,
+        "抓拍区域".to_string(),
+    );
     Box::new(comp)
 }
 
-fn test_create_circle_component(id: u32, x: i32, y: i32, radius: u32) -> Box<dyn Component> {
-    let control_width = 8;
-
-    let style = ComponentStyle {
-        font: "16px serif".to_string(),
-        line_width: 2,
-        line_color: "blue".to_string(),
-        line_focus_color: "red".to_string(),
-        control_line_width: 2,
-        control_width: 8,
-        control_line_color: "blue".to_string(),
-        control_fill_color: "red".to_string(),
-    };
-
-    let comp = CircleComponent {
+fn test_create_line_component(
+    id: u32,
+    x1: i32,
+    y1: i32,
+    x2: i32,
+    y2: i32,
+    style: &ComponentStyle,
+) -> Box<dyn Component> {
+    let comp = LineComponent::new(
         id,
+        Point { x: x1, y: y1 },
+        Point { x: x2, y: y2 },
         style,
-        title: "圆形".to_string(),
+        "边界线".to_string(),
+    );
+    Box::new(comp)
+}
 
-        start_control: ControlPoint {
-            point: Point { x, y },
-            width: control_width,
-            selected: false
-        },
-        end_control: ControlPoint {
-            point: Point {
-                x: x + radius as i32,
-                y,
-            },
-            width: control_width,
-            selected: false
+fn test_create_circle_component(
+    id: u32,
+    x: i32,
+    y: i32,
+    radius: u32,
+    style: &ComponentStyle,
+) -> Box<dyn Component> {
+    let comp = CircleComponent::new(
+        id,
+        Point { x, y },
+        Point {
+            x: x + radius as i32,
+            y,
         },
-
-        radius,
-        selected: false,
-    };
+        style,
+        "圆形".to_string(),
+    );
     Box::new(comp)
 }
 
@@ -454,10 +1674,10 @@ fn window() -> web_sys::Window {
     web_sys::window().expect("no global `window` exists")
 }
 
-fn request_animation_frame(f: &Closure<dyn FnMut()>) {
+fn request_animation_frame(f: &Closure<dyn FnMut()>) -> i32 {
     window()
         .request_animation_frame(f.as_ref().unchecked_ref())
-        .expect("should register `requestAnimationFrame` OK");
+        .expect("should register `requestAnimationFrame` OK")
 }
 
 fn document() -> web_sys::Document {
@@ -466,6 +1686,158 @@ fn document() -> web_sys::Document {
         .expect("should have a document on window")
 }
 
+fn get_element<T: JsCast>(id: &str, expected: &'static str) -> Result<T, FyError> {
+    document()
+        .get_element_by_id(id)
+        .ok_or_else(|| FyError::ElementNotFound(id.to_string()))?
+        .dyn_into::<T>()
+        .map_err(|_| FyError::UnexpectedElement {
+            id: id.to_string(),
+            expected,
+        })
+}
+
+fn context_2d(canvas: &web_sys::HtmlCanvasElement) -> Result<web_sys::CanvasRenderingContext2d, FyError> {
+    canvas
+        .get_context("2d")
+        .ok()
+        .flatten()
+        .and_then(|context| context.dyn_into::<web_sys::CanvasRenderingContext2d>().ok())
+        .ok_or(FyError::ContextUnavailable)
+}
+
+fn event_target<T: JsCast>(event: &web_sys::Event) -> Result<T, FyError> {
+    event
+        .target()
+        .and_then(|target| target.dyn_into::<T>().ok())
+        .ok_or_else(|| FyError::Js(format!("unexpected event target: {}", event.type_())))
+}
+
+/// 读取图片文件，结果通过 FileReader 的 load 事件返回
+fn read_image_file(file_reader: &web_sys::FileReader, file: &web_sys::File) -> Result<(), FyError> {
+    // 只接受图片文件
+    if !file.type_().starts_with("image/") {
+        return Err(FyError::ImageDecodeFailed(format!("not an image: {}", file.type_())));
+    }
+    file_reader
+        .read_as_data_url(file)
+        .map_err(|err| FyError::ImageDecodeFailed(format!("{:?}", err)))
+}
+
 fn body() -> web_sys::HtmlElement {
     document().body().expect("document should have a body")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Shape;
+
+    fn assert_near(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{} != {}", actual, expected);
+    }
+
+    // canvas 800x600，图片 1600x900
+    fn info(fit: BgFitMode, align: BgAlign) -> BgImgInfo {
+        BgImgInfo::new(800, 600, 1600, 900, fit, align)
+    }
+
+    #[test]
+    fn bg_contain() {
+        let bg_info = info(BgFitMode::Contain, BgAlign::Center);
+        assert_near(bg_info.scale_x, 0.5);
+        assert_near(bg_info.scale_y, 0.5);
+        assert_near(bg_info.dx, 0_f64);
+        assert_near(bg_info.dy, 75_f64);
+        assert_near(bg_info.width, 800_f64);
+        assert_near(bg_info.height, 450_f64);
+    }
+
+    #[test]
+    fn bg_cover() {
+        let bg_info = info(BgFitMode::Cover, BgAlign::Center);
+        assert_near(bg_info.scale_x, 600_f64 / 900_f64);
+        assert_near(bg_info.scale_y, 600_f64 / 900_f64);
+        assert_near(bg_info.dx, (800_f64 - 1600_f64 * 600_f64 / 900_f64) / 2_f64);
+        assert_near(bg_info.dy, 0_f64);
+        assert_near(bg_info.height, 600_f64);
+
+        let bg_info = info(BgFitMode::Cover, BgAlign::TopLeft);
+        assert_near(bg_info.dx, 0_f64);
+        assert_near(bg_info.dy, 0_f64);
+    }
+
+    #[test]
+    fn bg_stretch() {
+        let bg_info = info(BgFitMode::Stretch, BgAlign::Center);
+        assert_near(bg_info.scale_x, 0.5);
+        assert_near(bg_info.scale_y, 600_f64 / 900_f64);
+        assert_near(bg_info.dx, 0_f64);
+        assert_near(bg_info.dy, 0_f64);
+        assert_near(bg_info.width, 800_f64);
+        assert_near(bg_info.height, 600_f64);
+    }
+
+    #[test]
+    fn bg_actual_size() {
+        let bg_info = info(BgFitMode::ActualSize, BgAlign::Center);
+        assert_near(bg_info.scale_x, 1_f64);
+        assert_near(bg_info.scale_y, 1_f64);
+        assert_near(bg_info.dx, -400_f64);
+        assert_near(bg_info.dy, -150_f64);
+
+        let bg_info = info(BgFitMode::ActualSize, BgAlign::TopLeft);
+        assert_near(bg_info.dx, 0_f64);
+        assert_near(bg_info.dy, 0_f64);
+        assert_near(bg_info.width, 1600_f64);
+    }
+
+    #[test]
+    fn bg_round_trip() {
+        let fits = [BgFitMode::Contain, BgFitMode::Cover, BgFitMode::Stretch, BgFitMode::ActualSize];
+        for fit in fits {
+            for align in [BgAlign::TopLeft, BgAlign::Center] {
+                let bg_info = info(fit, align);
+                for (x, y) in [(0_f64, 0_f64), (123.5, 456.25), (1600_f64, 900_f64)] {
+                    let (c_x, c_y) = bg_info.to_canvas(x, y);
+                    let (i_x, i_y) = bg_info.to_image(c_x, c_y);
+                    assert_near(i_x, x);
+                    assert_near(i_y, y);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn remap_without_drift() {
+        let style = ComponentStyle::default();
+        let mut childs: ComponentVec = HashMap::new();
+        let rect = RectComponent::new(1, Point { x: 101, y: 203 }, Point { x: 333, y: 407 }, &style, String::new());
+        let circle = CircleComponent::new(2, Point { x: 417, y: 311 }, Point { x: 450, y: 311 }, &style, String::new());
+        childs.insert(1, Box::new(rect));
+        childs.insert(2, Box::new(circle));
+        let original: Vec<(Vec<Point>, Shape)> = [1, 2]
+            .iter()
+            .map(|id| (childs[id].control_points(), childs[id].shape()))
+            .collect();
+
+        let mut image_points = ImagePoints::default();
+        let mut old_info = info(BgFitMode::Contain, BgAlign::Center);
+        for _ in 0..10 {
+            for fit in [BgFitMode::Cover, BgFitMode::Stretch, BgFitMode::ActualSize, BgFitMode::Contain] {
+                let new_info = info(fit, BgAlign::Center);
+                image_points.remap(&mut childs, &old_info, &new_info);
+                old_info = new_info;
+            }
+        }
+
+        for (id, (points, shape)) in [1, 2].iter().zip(original) {
+            assert_eq!(childs[id].control_points(), points);
+            if let (Shape::Circle { radius, .. }, Shape::Circle { radius: expected, .. }) =
+                (childs[id].shape(), shape)
+            {
+                assert_near(radius, expected);
+            }
+        }
+    }
+}
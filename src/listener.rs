@@ -0,0 +1,56 @@
+use std::any::Any;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// 注册到 dom 上的事件回调
+/// 持有 closure 的所有权，drop 的时候移除监听并释放 closure
+pub struct Listener {
+    target: web_sys::EventTarget,
+    event_type: &'static str,
+    callback: js_sys::Function,
+    _closure: Box<dyn Any>,
+}
+
+impl Listener {
+    pub fn add<T: ?Sized + 'static>(
+        target: &web_sys::EventTarget,
+        event_type: &'static str,
+        closure: Closure<T>,
+    ) -> Result<Listener, JsValue> {
+        let callback = closure.as_ref().unchecked_ref::<js_sys::Function>().clone();
+        target.add_event_listener_with_callback(event_type, &callback)?;
+
+        Ok(Listener {
+            target: target.clone(),
+            event_type,
+            callback,
+            _closure: Box::new(closure),
+        })
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        let _ = self
+            .target
+            .remove_event_listener_with_callback(self.event_type, &self.callback);
+    }
+}
+
+/// 按 bind_* 分组保存事件回调
+/// 重复 bind 时替换同一分组，之前的监听被移除，保证不会重复注册
+#[derive(Default)]
+pub struct Listeners {
+    groups: HashMap<&'static str, Vec<Listener>>,
+}
+
+impl Listeners {
+    pub fn replace(&mut self, group: &'static str, listeners: Vec<Listener>) {
+        self.groups.insert(group, listeners);
+    }
+
+    pub fn clear(&mut self) {
+        self.groups.clear();
+    }
+}
@@ -0,0 +1,125 @@
+use std::cell::{Cell, RefCell};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = console, js_name = error)]
+    fn console_error(s: &str);
+
+    #[wasm_bindgen(js_namespace = console, js_name = warn)]
+    fn console_warn(s: &str);
+
+    #[wasm_bindgen(js_namespace = console, js_name = info)]
+    fn console_info(s: &str);
+
+    #[wasm_bindgen(js_namespace = console, js_name = debug)]
+    fn console_debug(s: &str);
+
+    #[wasm_bindgen(js_namespace = console, js_name = log)]
+    fn console_log(s: &str);
+}
+
+/// 日志级别，Off 关闭所有日志
+#[wasm_bindgen]
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub enum LogLevel {
+    Off = 0,
+    Error = 1,
+    Warn = 2,
+    Info = 3,
+    Debug = 4,
+    Trace = 5,
+}
+
+impl LogLevel {
+    fn name(&self) -> &'static str {
+        match self {
+            LogLevel::Off => "off",
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+            LogLevel::Trace => "trace",
+        }
+    }
+}
+
+// 日志设置是全局的，所有 FyCanvas 共用
+thread_local! {
+    static LEVEL: Cell<LogLevel> = const {
+        Cell::new(if cfg!(debug_assertions) {
+            LogLevel::Debug
+        } else {
+            LogLevel::Warn
+        })
+    };
+    static HOOK: RefCell<Option<js_sys::Function>> = const { RefCell::new(None) };
+}
+
+pub fn set_level(level: LogLevel) {
+    LEVEL.with(|current| current.set(level));
+}
+
+/// 设置 js 回调 hook(level, message)，设置后日志不再输出到 console
+pub fn set_hook(hook: Option<js_sys::Function>) {
+    HOOK.with(|current| *current.borrow_mut() = hook);
+}
+
+pub fn enabled(level: LogLevel) -> bool {
+    level != LogLevel::Off && LEVEL.with(|current| level <= current.get())
+}
+
+pub fn write(level: LogLevel, msg: &str) {
+    let hooked = HOOK.with(|hook| match hook.borrow().as_ref() {
+        Some(hook) => {
+            let _ = hook.call2(
+                &JsValue::NULL,
+                &JsValue::from_str(level.name()),
+                &JsValue::from_str(msg),
+            );
+            true
+        }
+        None => false,
+    });
+    if hooked {
+        return;
+    }
+
+    match level {
+        LogLevel::Off => {}
+        LogLevel::Error => console_error(msg),
+        LogLevel::Warn => console_warn(msg),
+        LogLevel::Info => console_info(msg),
+        LogLevel::Debug => console_debug(msg),
+        LogLevel::Trace => console_log(msg),
+    }
+}
+
+// 没有开启 logging feature 时 cfg! 为 false，整个分支被编译器去掉
+macro_rules! log_at {
+    ($level:expr, $($arg:tt)*) => {
+        if cfg!(feature = "logging") && $crate::logger::enabled($level) {
+            $crate::logger::write($level, &format!($($arg)*));
+        }
+    };
+}
+
+macro_rules! log_error {
+    ($($arg:tt)*) => { log_at!($crate::logger::LogLevel::Error, $($arg)*) };
+}
+
+macro_rules! log_warn {
+    ($($arg:tt)*) => { log_at!($crate::logger::LogLevel::Warn, $($arg)*) };
+}
+
+macro_rules! log_info {
+    ($($arg:tt)*) => { log_at!($crate::logger::LogLevel::Info, $($arg)*) };
+}
+
+macro_rules! log_debug {
+    ($($arg:tt)*) => { log_at!($crate::logger::LogLevel::Debug, $($arg)*) };
+}
+
+macro_rules! log_trace {
+    ($($arg:tt)*) => { log_at!($crate::logger::LogLevel::Trace, $($arg)*) };
+}
@@ -1,15 +1,96 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::ops::{Deref};
 use std::rc::Rc;
-use crate::{BgImgInfo, ComponentVec, log};
+use crate::component::{DragModifiers, Point};
+use crate::error::FyError;
+use crate::geometry::Units;
+use crate::label::{self, LabelRect};
+use crate::snap::{self, SnapConfig, SnapGuide};
+use crate::validate::{self, ValidationRules};
+use crate::{BgImgInfo, ComponentVec};
+
+/// 控制点限制在 rect 里
+/// 整体平移时限制平移的距离，保持形状不变，例如拖拽圆心时半径不变；其他拖拽分别限制每个控制点
+fn clamp_points(before: &[Point], after: &[Point], rect: LabelRect) -> Vec<Point> {
+    let (r_x, r_y, r_width, r_height) = rect;
+    let (left, top) = (r_x.ceil() as i32, r_y.ceil() as i32);
+    let right = ((r_x + r_width).floor() as i32).max(left);
+    let bottom = ((r_y + r_height).floor() as i32).max(top);
+
+    let delta = match (before.first(), after.first()) {
+        (Some(b), Some(a)) if before.len() == after.len() => Some((a.x - b.x, a.y - b.y)),
+        _ => None,
+    };
+    let translated = delta.filter(|(dx, dy)| {
+        before
+            .iter()
+            .zip(after.iter())
+            .all(|(b, a)| a.x - b.x == *dx && a.y - b.y == *dy)
+    });
+
+    match translated {
+        Some((dx, dy)) => {
+            // 每个控制点允许的平移范围取交集，放不下时不限制
+            let clamp_axis = |delta: i32, low: i32, high: i32, coords: &mut dyn Iterator<Item = i32>| {
+                let (mut min, mut max) = (i32::MIN, i32::MAX);
+                for coord in coords {
+                    min = min.max(low - coord);
+                    max = max.min(high - coord);
+                }
+                if min <= max {
+                    delta.clamp(min, max)
+                } else {
+                    delta
+                }
+            };
+            let dx = clamp_axis(dx, left, right, &mut before.iter().map(|p| p.x));
+            let dy = clamp_axis(dy, top, bottom, &mut before.iter().map(|p| p.y));
+            before
+                .iter()
+                .map(|p| Point {
+                    x: p.x + dx,
+                    y: p.y + dy,
+                })
+                .collect()
+        }
+        None => after
+            .iter()
+            .map(|p| Point {
+                x: p.x.clamp(left, right),
+                y: p.y.clamp(top, bottom),
+            })
+            .collect(),
+    }
+}
+
+/// 背景来源，静态图片或者视频
+pub enum BgSource {
+    Image(web_sys::HtmlImageElement),
+    Video(web_sys::HtmlVideoElement),
+}
 
 pub struct FyRender {
     pub canvas_ctx: web_sys::CanvasRenderingContext2d,
     pub cache_canvas: web_sys::HtmlCanvasElement,
     pub cache_ctx: web_sys::CanvasRenderingContext2d,
 
+    pub bg_source: Option<BgSource>,
+    // 背景在 canvas 上的位置，和 FyCanvas 共用，画背景和计算可见的图片区域时使用
+    pub bg_info: Rc<Cell<Option<BgImgInfo>>>,
+
     pub select_id: Option<u32>,
     pub mouse_pressed: bool,
+    // 当前选中的控件，松开鼠标后仍然保持，复制、剪切时使用
+    pub focus_id: Option<u32>,
+    // 鼠标悬停的控件和对应的 cursor
+    pub hover: Option<(u32, &'static str)>,
+
+    pub snap: SnapConfig,
+    // 拖拽时把控制点限制在图片区域内
+    pub clamp_to_image: bool,
+    pub rules: ValidationRules,
+    // 拖拽时当前的吸附辅助线
+    pub snap_guides: Vec<SnapGuide>,
 }
 
 impl FyRender {
@@ -17,55 +98,189 @@ impl FyRender {
         canvas_ctx: web_sys::CanvasRenderingContext2d,
         cache_canvas: web_sys::HtmlCanvasElement,
         cache_ctx: web_sys::CanvasRenderingContext2d,
+        bg_info: Rc<Cell<Option<BgImgInfo>>>,
     ) -> Self {
         Self {
             canvas_ctx,
             cache_canvas,
             cache_ctx,
+            bg_source: None,
+            bg_info,
             select_id: None,
             mouse_pressed: false,
+            focus_id: None,
+            hover: None,
+            snap: SnapConfig::default(),
+            clamp_to_image: false,
+            rules: ValidationRules::default(),
+            snap_guides: Vec::new(),
+        }
+    }
+
+    pub fn update_bg(&mut self, image: &web_sys::HtmlImageElement) -> Result<(), FyError> {
+        self.bg_source = Some(BgSource::Image(image.clone()));
+        self.draw_bg()
+    }
+
+    pub fn update_bg_video(&mut self, video: &web_sys::HtmlVideoElement) -> Result<(), FyError> {
+        self.bg_source = Some(BgSource::Video(video.clone()));
+        self.draw_bg()
+    }
+
+    pub fn bg_video(&self) -> Option<&web_sys::HtmlVideoElement> {
+        match &self.bg_source {
+            Some(BgSource::Video(video)) => Some(video),
+            _ => None,
         }
     }
 
-    pub fn update_bg(&self, image: &web_sys::HtmlImageElement, bg_info: &BgImgInfo) {
-        let img_width = image.width();
-        let img_height = image.height();
+    /// 按 bg_info 把背景画到 cache canvas 上，视频背景画的是当前帧
+    pub fn draw_bg(&self) -> Result<(), FyError> {
+        let (source, bg_info) = match (&self.bg_source, self.bg_info.get()) {
+            (Some(source), Some(bg_info)) => (source, bg_info),
+            _ => return Ok(()),
+        };
 
         let width = self.cache_canvas.width() as f64;
         let height = self.cache_canvas.height() as f64;
 
         self.cache_ctx.clear_rect(0.0, 0.0, width, height);
-        self.cache_ctx
-            .draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
-                image,
-                0.0,
-                0.0,
-                img_width as f64,
-                img_height as f64,
-                bg_info.dx,
-                bg_info.dy,
-                bg_info.width,
-                bg_info.height,
-            )
-            .unwrap();
-    }
-
-
-    pub fn paint(&self, childs: Rc<RefCell<ComponentVec>>) {
+        match source {
+            BgSource::Image(image) => self
+                .cache_ctx
+                .draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+                    image,
+                    0.0,
+                    0.0,
+                    image.width() as f64,
+                    image.height() as f64,
+                    bg_info.dx,
+                    bg_info.dy,
+                    bg_info.width,
+                    bg_info.height,
+                ),
+            BgSource::Video(video) => self
+                .cache_ctx
+                .draw_image_with_html_video_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+                    video,
+                    0.0,
+                    0.0,
+                    video.video_width() as f64,
+                    video.video_height() as f64,
+                    bg_info.dx,
+                    bg_info.dy,
+                    bg_info.width,
+                    bg_info.height,
+                ),
+        }
+        .map_err(|err| FyError::ImageDecodeFailed(format!("{:?}", err)))
+    }
+
+
+    pub fn paint(&self, childs: Rc<RefCell<ComponentVec>>) -> Result<(), FyError> {
         let width = self.cache_canvas.width() as f64;
         let height = self.cache_canvas.height() as f64;
 
         self.canvas_ctx.clear_rect(0.0, 0.0, width, height);
 
         self.canvas_ctx
-            .draw_image_with_html_canvas_element(&self.cache_canvas, 0.0, 0.0)
-            .unwrap();
+            .draw_image_with_html_canvas_element(&self.cache_canvas, 0.0, 0.0)?;
 
+        let visible = self.visible_rect();
         for component in childs.borrow().values() {
+            if !component.flags().visible {
+                continue;
+            }
             component.paint(&self.canvas_ctx);
+            label::paint_title(&self.canvas_ctx, component.as_ref(), visible);
+        }
+        for guide in self.snap_guides.iter() {
+            guide.paint(&self.canvas_ctx);
+        }
+        Ok(())
+    }
+
+    /// canvas 上可见的图片区域，没有背景时是整个 canvas
+    pub fn visible_rect(&self) -> LabelRect {
+        let width = self.cache_canvas.width() as f64;
+        let height = self.cache_canvas.height() as f64;
+
+        match self.bg_info.get() {
+            Some(bg_info) => {
+                let left = bg_info.dx.max(0_f64);
+                let top = bg_info.dy.max(0_f64);
+                let right = (bg_info.dx + bg_info.width).min(width);
+                let bottom = (bg_info.dy + bg_info.height).min(height);
+                (left, top, right - left, bottom - top)
+            }
+            None => (0_f64, 0_f64, width, height),
         }
     }
 
+    /// 校验规则的单位，有背景时是原图像素
+    pub fn units(&self) -> Units {
+        self.bg_info.get().map_or(Units::CANVAS, |bg_info| Units::image(&bg_info))
+    }
+
+    /// 图片在 canvas 上的区域，可能超出 canvas，没有背景时返回 None
+    pub fn image_rect(&self) -> Option<LabelRect> {
+        self.bg_info
+            .get()
+            .map(|bg_info| (bg_info.dx, bg_info.dy, bg_info.width, bg_info.height))
+    }
+
+    /// 超出图片区域的控件，没有背景时返回空
+    pub fn out_of_bounds(&self, childs: &Rc<RefCell<ComponentVec>>) -> Vec<u32> {
+        let (x, y, width, height) = match self.image_rect() {
+            Some(rect) => rect,
+            None => return Vec::new(),
+        };
+
+        let mut ids: Vec<u32> = childs
+            .borrow()
+            .iter()
+            .filter(|(_id, component)| {
+                let (b_x, b_y, b_width, b_height) = component.bounding_box(&Units::CANVAS);
+                b_x < x || b_y < y || b_x + b_width > x + width || b_y + b_height > y + height
+            })
+            .map(|(id, _component)| *id)
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// 双击位置上的控件，优先判断 title，然后判断控件本身
+    pub fn title_target(&self, childs: &Rc<RefCell<ComponentVec>>, x: f64, y: f64) -> Option<u32> {
+        let component_list = childs.borrow();
+        let visible = self.visible_rect();
+
+        // 锁定的控件不能修改 title
+        let editable = || {
+            component_list.iter().filter(|(_id, component)| {
+                let flags = component.flags();
+                flags.hittable() && !flags.locked
+            })
+        };
+
+        let on_title = editable().find(|(_id, component)| {
+            match label::title_box(&self.canvas_ctx, component.as_ref(), visible) {
+                Some((t_x, t_y, t_width, t_height)) => {
+                    x >= t_x && x <= t_x + t_width && y >= t_y && y <= t_y + t_height
+                }
+                None => false,
+            }
+        });
+
+        on_title
+            .or_else(|| editable().find(|(_id, component)| component.contains(x, y)))
+            .map(|(id, _component)| *id)
+    }
+
+    /// 有控件需要持续重画
+    pub fn animating(&self, childs: &Rc<RefCell<ComponentVec>>) -> bool {
+        childs.borrow().values().any(|component| component.animating())
+    }
+
     pub fn mouse_down(&mut self, childs: Rc<RefCell<ComponentVec>>, x: i32, y: i32) {
         if self.mouse_pressed {
             // mouse down 之前， pressed应该是false状态
@@ -82,37 +297,173 @@ impl FyRender {
         // 寻找选中的控件，设置成focus，其他控件失去focus
         // 判断 是点中控制点，还是移动区域
         let mut component_list = childs.deref().borrow_mut();
+        // 隐藏、不能选中的控件不响应鼠标，锁定的控件不能拖拽控制点
         for (id, component) in component_list.iter_mut() {
-            if self.select_id.is_none() && component.try_select(x, y) {
+            let flags = component.flags();
+            if self.select_id.is_none()
+                && flags.hittable()
+                && !flags.locked
+                && component.try_select(x, y)
+            {
                 self.select_id = Some(*id);
                 self.mouse_pressed = true;
                 component.set_select(true);
-                log(&format!("do select {}", *id));
+                log_debug!("do select {}", *id);
             } else {
                 component.set_select(false);
             }
         }
+
+        // 没有点中控制点时，点中控件本身也算选中，只是不拖拽
+        self.focus_id = self.select_id.or_else(|| {
+            component_list
+                .iter()
+                .find(|(_id, component)| {
+                    component.flags().hittable() && component.contains(x as f64, y as f64)
+                })
+                .map(|(id, _component)| *id)
+        });
+        if let Some(component) = self.focus_id.and_then(|id| component_list.get_mut(&id)) {
+            component.set_select(true);
+        }
+    }
+
+    /// 设置当前选中的控件，None 取消选中
+    pub fn set_focus(&mut self, childs: &Rc<RefCell<ComponentVec>>, id: Option<u32>) {
+        self.focus_id = id;
+        for (component_id, component) in childs.borrow_mut().iter_mut() {
+            component.set_select(Some(*component_id) == id);
+        }
+    }
+
+    /// undo/redo 删除控件之后，清掉指向已经不存在的控件的选中和 hover 状态
+    pub fn forget_removed(&mut self, childs: &Rc<RefCell<ComponentVec>>) {
+        let component_list = childs.borrow();
+        let removed = |id: u32| !component_list.contains_key(&id);
+
+        if self.focus_id.is_some_and(removed) {
+            self.focus_id = None;
+        }
+        if self.select_id.is_some_and(removed) {
+            self.select_id = None;
+            self.mouse_pressed = false;
+            self.snap_guides.clear();
+        }
+        if self.hover.is_some_and(|(id, _cursor)| removed(id)) {
+            self.hover = None;
+        }
     }
 
-    pub fn mouse_move(&mut self, childs: Rc<RefCell<ComponentVec>>, x: i32, y: i32) {
+    pub fn mouse_move(
+        &mut self,
+        childs: Rc<RefCell<ComponentVec>>,
+        x: i32,
+        y: i32,
+        modifiers: DragModifiers,
+    ) {
         // 选中控件的控制点，并拖拽
         if self.mouse_pressed {
             if let Some(id) = self.select_id {
-                if let Some(component) = childs.deref().borrow_mut().get_mut(&id) {
-                    log(&format!("move id: {}", component.id()));
-                    component.update_mouse(x, y);
+                let mut component_list = childs.deref().borrow_mut();
+                let origin = self
+                    .bg_info
+                    .get()
+                    .map_or((0_f64, 0_f64), |bg_info| (bg_info.dx, bg_info.dy));
+                let (point, guides) = snap::snap(
+                    &self.snap,
+                    &component_list,
+                    id,
+                    self.visible_rect(),
+                    origin,
+                    x,
+                    y,
+                );
+                self.snap_guides = guides;
+
+                if let Some(component) = component_list.get_mut(&id) {
+                    log_trace!("move id: {}", component.id());
+                    let before = component.control_points();
+                    let units = self.units();
+                    let issues_before = if self.rules.enforce_live {
+                        self.rules.check_component(component.as_ref(), &units)
+                    } else {
+                        Vec::new()
+                    };
+                    component.update_mouse(point.x, point.y, modifiers);
+
+                    // alt 缩放时另一个控制点也会移动，所以更新之后统一限制
+                    if let Some(rect) = self.image_rect().filter(|_rect| self.clamp_to_image) {
+                        let after = component.control_points();
+                        component.set_control_points(&clamp_points(&before, &after, rect));
+                    }
+
+                    // 让问题变多或者变严重的移动不生效
+                    if self.rules.enforce_live {
+                        let issues = self.rules.check_component(component.as_ref(), &units);
+                        if validate::worsened(&issues_before, &issues) {
+                            log_trace!("move rejected: {:?}", issues);
+                            component.set_control_points(&before);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// 没有按下鼠标时的移动，更新 hover 状态
+    /// 悬停的控件或者 cursor 变化时返回新的 cursor，需要重画
+    pub fn mouse_hover(&mut self, childs: Rc<RefCell<ComponentVec>>, x: i32, y: i32) -> Option<&'static str> {
+        let mut hover = None;
+
+        // 和 mouse_down 一样，第一个命中的控件优先
+        let mut component_list = childs.deref().borrow_mut();
+        for (id, component) in component_list.iter_mut() {
+            let flags = component.flags();
+            if hover.is_none() && flags.hittable() {
+                // 锁定的控件只提示不能修改，不高亮控制点
+                if flags.locked {
+                    if component.contains(x as f64, y as f64) {
+                        component.clear_hover();
+                        hover = Some((*id, "not-allowed"));
+                        continue;
+                    }
+                } else if let Some(cursor) = component.hover(x, y) {
+                    hover = Some((*id, cursor));
+                    continue;
                 }
             }
+            component.clear_hover();
+        }
+
+        if hover == self.hover {
+            return None;
+        }
+        log_trace!("hover {:?}", hover);
+        self.hover = hover;
+        Some(hover.map_or("default", |(_id, cursor)| cursor))
+    }
+
+    /// 鼠标离开 canvas，清掉 hover 状态，原来有 hover 时返回 true
+    pub fn clear_hover(&mut self, childs: Rc<RefCell<ComponentVec>>) -> bool {
+        let mut component_list = childs.deref().borrow_mut();
+        for (_id, component) in component_list.iter_mut() {
+            component.clear_hover();
         }
+        self.hover.take().is_some()
     }
 
     pub fn mouse_up(&mut self, childs: Rc<RefCell<ComponentVec>>, _x: i32, _y: i32) {
         self.mouse_pressed = false;
         self.select_id = None;
+        self.snap_guides.clear();
 
+        // 清掉控制点的拖拽状态，保留选中的控件
         let mut component_list = childs.deref().borrow_mut();
         for (_id, component) in component_list.iter_mut() {
             component.set_select(false);
         }
+        if let Some(component) = self.focus_id.and_then(|id| component_list.get_mut(&id)) {
+            component.set_select(true);
+        }
     }
 }
\ No newline at end of file
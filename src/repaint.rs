@@ -0,0 +1,89 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::error::ErrorHandler;
+use crate::render::FyRender;
+use crate::{window, ComponentVec};
+
+/// 合并重画请求
+/// 鼠标事件只设置 dirty 标志，同一帧内只请求一次 animation frame，
+/// 回调闭包只创建一次，重复使用
+pub struct Repaint {
+    render: Rc<RefCell<FyRender>>,
+    childs: Rc<RefCell<ComponentVec>>,
+    on_error: ErrorHandler,
+
+    // 已经请求但还没执行的 animation frame，Some 即 dirty
+    frame_id: Cell<Option<i32>>,
+    callback: RefCell<Option<Closure<dyn FnMut()>>>,
+}
+
+impl Repaint {
+    pub fn new(
+        render: Rc<RefCell<FyRender>>,
+        childs: Rc<RefCell<ComponentVec>>,
+        on_error: ErrorHandler,
+    ) -> Rc<Repaint> {
+        let repaint = Rc::new(Repaint {
+            render,
+            childs,
+            on_error,
+            frame_id: Cell::new(None),
+            callback: RefCell::new(None),
+        });
+
+        // 闭包只持有 weak 引用，避免循环引用
+        let weak = Rc::downgrade(&repaint);
+        let closure = Closure::wrap(Box::new(move || {
+            if let Some(repaint) = weak.upgrade() {
+                repaint.frame_id.set(None);
+                repaint.paint();
+            }
+        }) as Box<dyn FnMut()>);
+        *repaint.callback.borrow_mut() = Some(closure);
+
+        repaint
+    }
+
+    /// 标记需要重画，这一帧已经请求过的话不再重复请求
+    pub fn request(&self) {
+        if self.frame_id.get().is_some() {
+            return;
+        }
+
+        if let Some(callback) = self.callback.borrow().as_ref() {
+            let id = window()
+                .request_animation_frame(callback.as_ref().unchecked_ref())
+                .expect("should register `requestAnimationFrame` OK");
+            self.frame_id.set(Some(id));
+        }
+    }
+
+    /// 立即重画，例如视频逐帧时，同时取消还没执行的请求
+    pub fn paint_now(&self) {
+        self.cancel();
+        self.paint();
+    }
+
+    /// 取消还没执行的重画请求
+    pub fn cancel(&self) {
+        if let Some(id) = self.frame_id.take() {
+            let _ = window().cancel_animation_frame(id);
+        }
+    }
+
+    fn paint(&self) {
+        let render = self.render.borrow();
+        if let Err(err) = render.paint(self.childs.clone()) {
+            self.on_error.report(err);
+        }
+
+        // 流动虚线之类的动画，继续请求下一帧
+        if render.animating(&self.childs) {
+            drop(render);
+            self.request();
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::component::{
+    CircleComponent, Component, ComponentFlags, ComponentStyle, LineComponent, Metadata, Point, RectComponent,
+};
+use crate::error::FyError;
+
+/// 剪贴板里 json 的格式标记，粘贴时用来识别是不是控件数据
+pub const CLIPBOARD_FORMAT: &str = "wasm-draw/components";
+
+/// 控件的数据，用于复制粘贴和修改记录
+/// points 按类型: rect 是左上角和右下角，line 是两个端点，circle 是圆心和半径控制点
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComponentData {
+    // 导入时为 0 表示自动分配
+    #[serde(default)]
+    pub id: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub points: Vec<Point>,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub style_class: Option<String>,
+    // 没有时使用样式类或者 canvas 的默认样式
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub style: Option<ComponentStyle>,
+    #[serde(default, skip_serializing_if = "Metadata::is_empty")]
+    pub metadata: Metadata,
+    #[serde(default)]
+    pub flags: ComponentFlags,
+}
+
+impl ComponentData {
+    pub fn from_component(component: &dyn Component) -> Self {
+        Self {
+            id: component.id(),
+            external_id: component.external_id().map(|id| id.to_string()),
+            kind: component.shape().kind().to_string(),
+            points: component.control_points(),
+            title: component.title().to_string(),
+            style_class: component.style_class().map(|class| class.to_string()),
+            style: Some(component.style()),
+            metadata: component.metadata().clone(),
+            flags: component.flags(),
+        }
+    }
+
+    pub fn build(&self) -> Result<Box<dyn Component>, FyError> {
+        let (first, second) = match self.points.as_slice() {
+            [first, second] => (*first, *second),
+            _ => {
+                return Err(FyError::InvalidSceneJson(format!(
+                    "{} needs 2 points, got {}",
+                    self.kind,
+                    self.points.len()
+                )))
+            }
+        };
+
+        let title = self.title.clone();
+        let style = self.style.clone().unwrap_or_default();
+        let mut component: Box<dyn Component> = match self.kind.as_str() {
+            "rect" => Box::new(RectComponent::new(self.id, first, second, &style, title)),
+            "line" => Box::new(LineComponent::new(self.id, first, second, &style, title)),
+            "circle" => Box::new(CircleComponent::new(self.id, first, second, &style, title)),
+            kind => {
+                return Err(FyError::InvalidSceneJson(format!(
+                    "unknown component type: {}",
+                    kind
+                )))
+            }
+        };
+        component.set_style_class(self.style_class.clone());
+        component.set_external_id(self.external_id.clone());
+        *component.metadata_mut() = self.metadata.clone();
+        component.set_flags(self.flags);
+        Ok(component)
+    }
+
+    pub fn translate(&mut self, dx: i32, dy: i32) {
+        for point in self.points.iter_mut() {
+            point.x += dx;
+            point.y += dy;
+        }
+    }
+}
+
+/// 整个场景的数据，用于导入导出
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SceneData {
+    pub components: Vec<ComponentData>,
+}
+
+impl SceneData {
+    /// 检查 id 和 external_id 没有重复
+    pub fn check_ids(&self) -> Result<(), FyError> {
+        let mut ids = HashSet::new();
+        let mut external_ids = HashSet::new();
+        for component in self.components.iter() {
+            if component.id != 0 && !ids.insert(component.id) {
+                return Err(FyError::DuplicateId(component.id.to_string()));
+            }
+            if let Some(external_id) = &component.external_id {
+                if !external_ids.insert(external_id.as_str()) {
+                    return Err(FyError::DuplicateId(external_id.clone()));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 复制到剪贴板的内容
+#[derive(Debug, Serialize, Deserialize)]
+struct ClipboardData {
+    format: String,
+    components: Vec<ComponentData>,
+}
+
+pub fn to_clipboard(components: Vec<ComponentData>) -> Result<String, FyError> {
+    let data = ClipboardData {
+        format: CLIPBOARD_FORMAT.to_string(),
+        components,
+    };
+    serde_json::to_string(&data).map_err(|err| FyError::Js(err.to_string()))
+}
+
+/// 解析剪贴板的内容，不是控件数据时返回 None
+pub fn from_clipboard(text: &str) -> Option<Vec<ComponentData>> {
+    let data: ClipboardData = serde_json::from_str(text).ok()?;
+    if data.format != CLIPBOARD_FORMAT {
+        return None;
+    }
+    Some(data.components)
+}
@@ -0,0 +1,209 @@
+use web_sys::CanvasRenderingContext2d;
+
+use crate::component::Point;
+use crate::label::LabelRect;
+use crate::ComponentVec;
+
+/// 吸附设置
+#[derive(Debug, Copy, Clone)]
+pub struct SnapConfig {
+    pub enabled: bool,
+    // 吸附距离，单位是 canvas 像素
+    pub threshold: f64,
+    // 网格大小，0 表示不吸附网格，网格原点是图片的左上角，图片超出 canvas 时也一样
+    pub grid: u32,
+    // 其他控件的控制点和边的中点
+    pub to_points: bool,
+    // 其他控件的边
+    pub to_edges: bool,
+    // 图片边框
+    pub to_border: bool,
+}
+
+impl Default for SnapConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: 8_f64,
+            grid: 0,
+            to_points: true,
+            to_edges: true,
+            to_border: true,
+        }
+    }
+}
+
+/// 吸附时画的辅助线
+#[derive(Debug, Copy, Clone)]
+pub enum SnapGuide {
+    // 吸附到的点
+    Point(f64, f64),
+    // 吸附到的线段，图片边框也是线段
+    Segment((f64, f64), (f64, f64)),
+}
+
+impl SnapGuide {
+    pub fn paint(&self, context: &CanvasRenderingContext2d) {
+        context.save();
+        context.set_stroke_style_str("magenta");
+        context.set_line_width(1_f64);
+
+        match *self {
+            SnapGuide::Point(x, y) => {
+                context.begin_path();
+                let _ = context.arc(x, y, 6_f64, 0_f64, std::f64::consts::PI * 2_f64);
+                context.stroke();
+            }
+            SnapGuide::Segment((x1, y1), (x2, y2)) => {
+                let _ = context.set_line_dash(&js_sys::Array::of2(&4.into(), &4.into()));
+                context.begin_path();
+                context.move_to(x1, y1);
+                context.line_to(x2, y2);
+                context.stroke();
+            }
+        }
+        context.restore();
+    }
+}
+
+// 点到线段最近的点和距离
+fn project(point: (f64, f64), a: (f64, f64), b: (f64, f64)) -> ((f64, f64), f64) {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length2 = dx * dx + dy * dy;
+    let t = if length2 == 0_f64 {
+        0_f64
+    } else {
+        (((point.0 - a.0) * dx + (point.1 - a.1) * dy) / length2).clamp(0_f64, 1_f64)
+    };
+    let nearest = (a.0 + t * dx, a.1 + t * dy);
+    (nearest, (point.0 - nearest.0).hypot(point.1 - nearest.1))
+}
+
+/// 拖拽 moving 控件时，把 (x, y) 吸附到附近的目标
+/// 优先级: 控制点/中点 > 边 > 图片边框 > 网格，边框和网格按 x、y 分别吸附
+/// border 是可见的图片区域，origin 是图片左上角在 canvas 上的位置，没有背景时是 (0, 0)
+pub fn snap(
+    config: &SnapConfig,
+    childs: &ComponentVec,
+    moving: u32,
+    border: LabelRect,
+    origin: (f64, f64),
+    x: i32,
+    y: i32,
+) -> (Point, Vec<SnapGuide>) {
+    let point = (x as f64, y as f64);
+    let unchanged = (Point { x, y }, Vec::new());
+    if !config.enabled {
+        return unchanged;
+    }
+    let threshold = config.threshold;
+    let to_point = |p: (f64, f64)| Point {
+        x: p.0.round() as i32,
+        y: p.1.round() as i32,
+    };
+
+    let others = || {
+        childs
+            .iter()
+            .filter(move |(id, component)| **id != moving && component.flags().visible)
+            .map(|(_id, component)| component)
+    };
+
+    // 控制点和边的中点
+    if config.to_points {
+        let mut nearest: Option<((f64, f64), f64)> = None;
+        for component in others() {
+            let points = component
+                .snap_points()
+                .into_iter()
+                .map(|p| (p.x as f64, p.y as f64));
+            let middles = component.snap_edges().into_iter().map(|(a, b)| {
+                ((a.x + b.x) as f64 / 2_f64, (a.y + b.y) as f64 / 2_f64)
+            });
+
+            for target in points.chain(middles) {
+                let distance = (point.0 - target.0).hypot(point.1 - target.1);
+                if distance <= threshold && nearest.is_none_or(|(_t, d)| distance < d) {
+                    nearest = Some((target, distance));
+                }
+            }
+        }
+        if let Some((target, _distance)) = nearest {
+            return (to_point(target), vec![SnapGuide::Point(target.0, target.1)]);
+        }
+    }
+
+    // 边
+    if config.to_edges {
+        let mut nearest: Option<((f64, f64), f64, SnapGuide)> = None;
+        for component in others() {
+            for (a, b) in component.snap_edges() {
+                let a = (a.x as f64, a.y as f64);
+                let b = (b.x as f64, b.y as f64);
+                let (target, distance) = project(point, a, b);
+                if distance <= threshold && nearest.is_none_or(|(_t, d, _g)| distance < d) {
+                    nearest = Some((target, distance, SnapGuide::Segment(a, b)));
+                }
+            }
+        }
+        if let Some((target, _distance, guide)) = nearest {
+            return (to_point(target), vec![guide]);
+        }
+    }
+
+    // 图片边框和网格，x、y 分别处理
+    let (b_x, b_y, b_width, b_height) = border;
+    let mut guides = Vec::new();
+    let mut snap_axis = |value: f64, low: f64, size: f64, start: f64, vertical: bool| -> f64 {
+        if config.to_border {
+            for edge in [low, low + size] {
+                if (value - edge).abs() <= threshold {
+                    guides.push(if vertical {
+                        SnapGuide::Segment((edge, b_y), (edge, b_y + b_height))
+                    } else {
+                        SnapGuide::Segment((b_x, edge), (b_x + b_width, edge))
+                    });
+                    return edge;
+                }
+            }
+        }
+        if config.grid > 0 {
+            let grid = config.grid as f64;
+            let edge = start + ((value - start) / grid).round() * grid;
+            if (value - edge).abs() <= threshold {
+                return edge;
+            }
+        }
+        value
+    };
+
+    let snapped = (
+        snap_axis(point.0, b_x, b_width, origin.0, true),
+        snap_axis(point.1, b_y, b_height, origin.1, false),
+    );
+    if snapped == point {
+        return unchanged;
+    }
+    if config.grid > 0 && guides.is_empty() {
+        guides.push(SnapGuide::Point(snapped.0, snapped.1));
+    }
+    (to_point(snapped), guides)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn grid_starts_at_image_origin() {
+        let config = SnapConfig {
+            enabled: true,
+            grid: 10,
+            ..SnapConfig::default()
+        };
+        // cover 模式下图片左上角在 canvas 外面，可见区域从 (0, 0) 开始
+        let (point, _guides) = snap(&config, &HashMap::new(), 1, (0.0, 0.0, 800.0, 600.0), (-3.0, 5.0), 18, 14);
+        assert_eq!(point, Point { x: 17, y: 15 });
+    }
+}
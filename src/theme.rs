@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+use crate::component::{ComponentStyle, FillPattern};
+use crate::label::LabelAnchor;
+use crate::scene::ComponentData;
+
+/// 内置主题，每个主题提供默认样式和一组样式类
+#[wasm_bindgen]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Theme {
+    Light,
+    Dark,
+    HighContrast,
+}
+
+// 内置样式类的名字
+pub const CLASS_INTRUSION: &str = "intrusion";
+pub const CLASS_EXCLUSION: &str = "exclusion";
+pub const CLASS_COUNTING_LINE: &str = "counting-line";
+
+struct Palette {
+    line_width: u32,
+    line_color: &'static str,
+    focus_color: &'static str,
+    control_line_color: &'static str,
+    control_fill_color: &'static str,
+
+    intrusion_color: &'static str,
+    exclusion_color: &'static str,
+    counting_line_color: &'static str,
+}
+
+fn palette(theme: Theme) -> Palette {
+    match theme {
+        Theme::Light => Palette {
+            line_width: 2,
+            line_color: "blue",
+            focus_color: "red",
+            control_line_color: "blue",
+            control_fill_color: "red",
+            intrusion_color: "blue",
+            exclusion_color: "#d32f2f",
+            counting_line_color: "green",
+        },
+        Theme::Dark => Palette {
+            line_width: 2,
+            line_color: "#90caf9",
+            focus_color: "#ffb74d",
+            control_line_color: "#e0e0e0",
+            control_fill_color: "#ffb74d",
+            intrusion_color: "#4fc3f7",
+            exclusion_color: "#ff8a65",
+            counting_line_color: "#aed581",
+        },
+        Theme::HighContrast => Palette {
+            line_width: 3,
+            line_color: "yellow",
+            focus_color: "magenta",
+            control_line_color: "white",
+            control_fill_color: "black",
+            intrusion_color: "yellow",
+            exclusion_color: "magenta",
+            counting_line_color: "cyan",
+        },
+    }
+}
+
+pub fn default_style(theme: Theme) -> ComponentStyle {
+    let palette = palette(theme);
+    ComponentStyle {
+        line_width: palette.line_width,
+        line_color: palette.line_color.to_string(),
+        line_focus_color: palette.focus_color.to_string(),
+        control_line_color: palette.control_line_color.to_string(),
+        control_fill_color: palette.control_fill_color.to_string(),
+        ..ComponentStyle::default()
+    }
+}
+
+pub fn style_classes(theme: Theme) -> HashMap<String, ComponentStyle> {
+    let palette = palette(theme);
+    let base = default_style(theme);
+
+    // 入侵区域: 半透明填充
+    let intrusion = ComponentStyle {
+        line_color: palette.intrusion_color.to_string(),
+        fill_color: palette.intrusion_color.to_string(),
+        fill_opacity: 0.15,
+        ..base.clone()
+    };
+
+    // 排除区域: 虚线加交叉斜线
+    let exclusion = ComponentStyle {
+        line_color: palette.exclusion_color.to_string(),
+        fill_color: palette.exclusion_color.to_string(),
+        fill_opacity: 0.5,
+        fill_pattern: FillPattern::Hatch,
+        line_dash: vec![8.0, 4.0],
+        ..base.clone()
+    };
+
+    // 计数线: 加粗
+    let counting_line = ComponentStyle {
+        line_color: palette.counting_line_color.to_string(),
+        line_width: palette.line_width + 1,
+        line_cap: "round".to_string(),
+        label_anchor: LabelAnchor::AboveMidpoint,
+        ..base
+    };
+
+    let mut classes = HashMap::new();
+    classes.insert(CLASS_INTRUSION.to_string(), intrusion);
+    classes.insert(CLASS_EXCLUSION.to_string(), exclusion);
+    classes.insert(CLASS_COUNTING_LINE.to_string(), counting_line);
+    classes
+}
+
+/// 新建控件使用的默认样式和样式类
+pub struct Styles {
+    pub default_style: ComponentStyle,
+    pub classes: HashMap<String, ComponentStyle>,
+}
+
+impl Styles {
+    pub fn new(theme: Theme) -> Self {
+        Self {
+            default_style: default_style(theme),
+            classes: style_classes(theme),
+        }
+    }
+
+    /// 控件数据没有样式时，使用引用的样式类，没有注册的样式类使用默认样式
+    pub fn fill(&self, component: &mut ComponentData) {
+        if component.style.is_some() {
+            return;
+        }
+        let style = component
+            .style_class
+            .as_ref()
+            .and_then(|name| self.classes.get(name))
+            .unwrap_or(&self.default_style);
+        component.style = Some(style.clone());
+    }
+}
@@ -0,0 +1,74 @@
+use std::cell::Cell;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// 双击编辑 title 用的 input，position: fixed 覆盖在 canvas 上
+/// input 只创建一次，编辑时显示，结束时隐藏；drop 时从 dom 移除
+pub struct TitleEditor {
+    input: web_sys::HtmlInputElement,
+    // 正在编辑的控件
+    editing: Cell<Option<u32>>,
+}
+
+impl TitleEditor {
+    pub fn new(document: &web_sys::Document) -> Result<TitleEditor, JsValue> {
+        let input = document
+            .create_element("input")?
+            .dyn_into::<web_sys::HtmlInputElement>()?;
+        input.set_type("text");
+
+        let style = input.style();
+        style.set_property("position", "fixed")?;
+        style.set_property("z-index", "1000")?;
+        style.set_property("display", "none")?;
+        style.set_property("box-sizing", "border-box")?;
+
+        document
+            .body()
+            .ok_or_else(|| JsError::new("document should have a body"))?
+            .append_child(&input)?;
+
+        Ok(TitleEditor {
+            input,
+            editing: Cell::new(None),
+        })
+    }
+
+    pub fn input(&self) -> &web_sys::HtmlInputElement {
+        &self.input
+    }
+
+    /// 在 (left, top) 打开编辑框，坐标是相对浏览器窗口的
+    pub fn open(&self, id: u32, title: &str, left: f64, top: f64, width: f64, font: &str) {
+        self.editing.set(Some(id));
+        self.input.set_value(title);
+
+        let style = self.input.style();
+        let _ = style.set_property("left", &format!("{}px", left));
+        let _ = style.set_property("top", &format!("{}px", top));
+        let _ = style.set_property("width", &format!("{}px", width));
+        let _ = style.set_property("font", font);
+        let _ = style.set_property("display", "block");
+
+        let _ = self.input.focus();
+        self.input.select();
+    }
+
+    /// 结束编辑，返回正在编辑的控件，已经结束过的返回 None
+    /// 先清掉 editing，隐藏 input 时触发的 blur 不会重复提交
+    pub fn close(&self) -> Option<u32> {
+        let id = self.editing.take();
+        let _ = self.input.style().set_property("display", "none");
+        id
+    }
+
+    pub fn value(&self) -> String {
+        self.input.value()
+    }
+}
+
+impl Drop for TitleEditor {
+    fn drop(&mut self) {
+        self.input.remove();
+    }
+}
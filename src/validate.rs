@@ -0,0 +1,319 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::component::Component;
+use crate::error::FyError;
+use crate::geometry::{self, Shape, Units};
+use crate::ComponentVec;
+
+/// 控件的校验规则，不设置的规则不检查
+/// 尺寸单位是原图像素，和后台分析用的一致，不受 canvas 大小和缩放方式影响；没有背景时是 canvas 像素
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ValidationRules {
+    pub min_width: Option<f64>,
+    pub min_height: Option<f64>,
+    pub min_radius: Option<f64>,
+    pub min_area: Option<f64>,
+    pub min_line_length: Option<f64>,
+    // 现在只有矩形、线和圆，顶点数固定，矩形也不会自相交
+    // max_vertices 和 no_self_intersection 先留着，有多边形控件之后才会触发
+    pub max_vertices: Option<usize>,
+    // 封闭区域的边不能自相交
+    pub no_self_intersection: bool,
+    // 每种控件的最大数量，key 是 rect/line/circle
+    pub max_per_type: HashMap<String, usize>,
+    // 编辑时就检查: 让问题变多或者变严重的移动不生效，超出数量的粘贴、复制报错
+    pub enforce_live: bool,
+}
+
+/// 校验发现的问题，id 为空表示不是某个控件的问题，例如数量超出
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationIssue {
+    pub id: Option<u32>,
+    pub rule: &'static str,
+    pub message: String,
+    // 和规则差了多少，拖拽时比较移动前后是否变严重
+    #[serde(skip)]
+    pub excess: f64,
+}
+
+impl ValidationIssue {
+    pub fn component(id: u32, rule: &'static str, message: String) -> Self {
+        Self {
+            id: Some(id),
+            rule,
+            message,
+            excess: 0_f64,
+        }
+    }
+
+    fn with_excess(mut self, excess: f64) -> Self {
+        self.excess = excess;
+        self
+    }
+}
+
+/// 移动之后出现了新的问题，或者已有的问题更严重了
+/// 已经不满足规则的控件，仍然可以往满足规则的方向拖拽
+pub fn worsened(before: &[ValidationIssue], after: &[ValidationIssue]) -> bool {
+    after.iter().any(|issue| {
+        match before.iter().find(|old| old.rule == issue.rule) {
+            Some(old) => issue.excess > old.excess,
+            None => true,
+        }
+    })
+}
+
+// 每种控件的数量
+fn counts(childs: &ComponentVec) -> HashMap<&'static str, usize> {
+    let mut counts = HashMap::new();
+    for component in childs.values() {
+        *counts.entry(component.shape().kind()).or_default() += 1;
+    }
+    counts
+}
+
+impl ValidationRules {
+    /// 检查单个控件的几何规则，units 是规则的尺寸单位
+    pub fn check_component(&self, component: &dyn Component, units: &Units) -> Vec<ValidationIssue> {
+        let id = component.id();
+        let shape = component.shape();
+        let mut issues = Vec::new();
+
+        let mut check_min = |rule: &'static str, limit: Option<f64>, value: f64| {
+            if let Some(limit) = limit {
+                if value < limit {
+                    issues.push(
+                        ValidationIssue::component(
+                            id,
+                            rule,
+                            format!("{} {:.1} is less than {:.1}", rule, value, limit),
+                        )
+                        .with_excess(limit - value),
+                    );
+                }
+            }
+        };
+
+        match shape {
+            Shape::Rect { .. } => {
+                let (_x, _y, width, height) = shape.bounds(units);
+                check_min("min_width", self.min_width, width);
+                check_min("min_height", self.min_height, height);
+            }
+            Shape::Line { .. } => {
+                check_min("min_line_length", self.min_line_length, shape.perimeter(units));
+            }
+            Shape::Circle { radius, .. } => {
+                // x、y 缩放不同时在原图上是椭圆，取短的半轴
+                let (a, b) = units.size(radius, radius);
+                check_min("min_radius", self.min_radius, a.min(b));
+            }
+        }
+        if shape.closed() {
+            check_min("min_area", self.min_area, shape.area(units));
+        }
+
+        let vertices = shape.vertices();
+        if let Some(max_vertices) = self.max_vertices {
+            if vertices.len() > max_vertices {
+                issues.push(
+                    ValidationIssue::component(
+                        id,
+                        "max_vertices",
+                        format!("{} vertices, at most {}", vertices.len(), max_vertices),
+                    )
+                    .with_excess((vertices.len() - max_vertices) as f64),
+                );
+            }
+        }
+        if self.no_self_intersection && shape.closed() && geometry::self_intersecting(&vertices) {
+            issues.push(ValidationIssue::component(
+                id,
+                "no_self_intersection",
+                "edges intersect each other".to_string(),
+            ));
+        }
+
+        issues
+    }
+
+    /// 检查所有控件，结果按控件 id 排序，数量超出的问题放在最后
+    pub fn check_all(&self, childs: &ComponentVec, units: &Units) -> Vec<ValidationIssue> {
+        let mut ids: Vec<&u32> = childs.keys().collect();
+        ids.sort_unstable();
+
+        let mut issues: Vec<ValidationIssue> = ids
+            .into_iter()
+            .flat_map(|id| self.check_component(childs[id].as_ref(), units))
+            .collect();
+
+        let counts = counts(childs);
+        let mut kinds: Vec<&String> = self.max_per_type.keys().collect();
+        kinds.sort_unstable();
+        for kind in kinds {
+            let max = self.max_per_type[kind];
+            let count = counts.get(kind.as_str()).copied().unwrap_or(0);
+            if count > max {
+                issues.push(ValidationIssue {
+                    id: None,
+                    rule: "max_per_type",
+                    message: format!("{} {} components, at most {}", count, kind, max),
+                    excess: (count - max) as f64,
+                });
+            }
+        }
+
+        issues
+    }
+
+    /// 添加控件之前检查每种控件的数量，kinds 是要添加的控件类型
+    pub fn check_add<'a>(
+        &self,
+        childs: &ComponentVec,
+        kinds: impl IntoIterator<Item = &'a str>,
+    ) -> Result<(), FyError> {
+        let mut added: HashMap<&str, usize> = HashMap::new();
+        for kind in kinds {
+            *added.entry(kind).or_default() += 1;
+        }
+
+        let counts = counts(childs);
+        for (kind, count) in added {
+            let max = match self.max_per_type.get(kind) {
+                Some(max) => *max,
+                None => continue,
+            };
+            let total = counts.get(kind).copied().unwrap_or(0) + count;
+            if total > max {
+                return Err(FyError::RuleViolated(format!(
+                    "{} {} components, at most {}",
+                    total, kind, max
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::{
+        CircleComponent, ComponentStyle, LineComponent, Point, RectComponent,
+    };
+
+    fn childs() -> ComponentVec {
+        let style = ComponentStyle::default();
+        let mut childs: ComponentVec = HashMap::new();
+        // 20x100 的矩形，长 5 的线，半径 30 的圆
+        let rect = RectComponent::new(1, Point { x: 0, y: 0 }, Point { x: 20, y: 100 }, &style, String::new());
+        let line = LineComponent::new(2, Point { x: 0, y: 0 }, Point { x: 3, y: 4 }, &style, String::new());
+        let circle = CircleComponent::new(3, Point { x: 50, y: 50 }, Point { x: 80, y: 50 }, &style, String::new());
+        childs.insert(1, Box::new(rect));
+        childs.insert(2, Box::new(line));
+        childs.insert(3, Box::new(circle));
+        childs
+    }
+
+    #[test]
+    fn no_rules_no_issues() {
+        assert!(ValidationRules::default().check_all(&childs(), &Units::CANVAS).is_empty());
+    }
+
+    #[test]
+    fn check_all_sorted_by_id() {
+        let mut rules = ValidationRules {
+            min_width: Some(50_f64),
+            min_line_length: Some(10_f64),
+            min_radius: Some(10_f64),
+            ..ValidationRules::default()
+        };
+        rules.max_per_type.insert("rect".to_string(), 0);
+
+        let issues = rules.check_all(&childs(), &Units::CANVAS);
+        let found: Vec<(Option<u32>, &str)> = issues.iter().map(|issue| (issue.id, issue.rule)).collect();
+        assert_eq!(
+            found,
+            vec![
+                (Some(1), "min_width"),
+                (Some(2), "min_line_length"),
+                (None, "max_per_type"),
+            ]
+        );
+        assert_eq!(issues[0].excess, 30_f64);
+        assert_eq!(issues[1].excess, 5_f64);
+    }
+
+    #[test]
+    fn min_area_only_for_closed_shapes() {
+        let rules = ValidationRules {
+            min_area: Some(1000_f64),
+            ..ValidationRules::default()
+        };
+        let ids: Vec<Option<u32>> = rules.check_all(&childs(), &Units::CANVAS).iter().map(|issue| issue.id).collect();
+        // 线不是封闭的区域，不检查面积
+        assert!(ids.is_empty());
+
+        let rules = ValidationRules {
+            min_area: Some(2500_f64),
+            ..ValidationRules::default()
+        };
+        let ids: Vec<Option<u32>> = rules.check_all(&childs(), &Units::CANVAS).iter().map(|issue| issue.id).collect();
+        assert_eq!(ids, vec![Some(1)]);
+    }
+
+    #[test]
+    fn sizes_in_image_pixels() {
+        use crate::{BgAlign, BgFitMode, BgImgInfo};
+
+        // 图片缩小一半显示，canvas 上宽 20 的矩形在原图上宽 40
+        let bg_info = BgImgInfo::new(800, 600, 1600, 1200, BgFitMode::Contain, BgAlign::TopLeft);
+        let rules = ValidationRules {
+            min_width: Some(30_f64),
+            min_radius: Some(50_f64),
+            ..ValidationRules::default()
+        };
+        let found: Vec<(Option<u32>, &str)> = rules
+            .check_all(&childs(), &Units::image(&bg_info))
+            .iter()
+            .map(|issue| (issue.id, issue.rule))
+            .collect();
+        assert_eq!(found, vec![]);
+
+        let found: Vec<(Option<u32>, &str)> = rules
+            .check_all(&childs(), &Units::CANVAS)
+            .iter()
+            .map(|issue| (issue.id, issue.rule))
+            .collect();
+        assert_eq!(found, vec![(Some(1), "min_width"), (Some(3), "min_radius")]);
+    }
+
+    #[test]
+    fn worsened_compares_with_before() {
+        let issue = |rule, excess| ValidationIssue::component(1, rule, String::new()).with_excess(excess);
+
+        // 已经不满足规则，往满足的方向移动可以
+        assert!(!worsened(&[issue("min_width", 30_f64)], &[issue("min_width", 25_f64)]));
+        assert!(!worsened(&[issue("min_width", 30_f64)], &[]));
+        // 变严重或者出现新的问题不行
+        assert!(worsened(&[issue("min_width", 30_f64)], &[issue("min_width", 35_f64)]));
+        assert!(worsened(&[issue("min_width", 30_f64)], &[issue("min_width", 25_f64), issue("min_height", 1_f64)]));
+        assert!(worsened(&[], &[issue("min_width", 1_f64)]));
+    }
+
+    #[test]
+    fn check_add_counts_existing() {
+        let mut rules = ValidationRules::default();
+        rules.max_per_type.insert("rect".to_string(), 2);
+
+        let childs = childs();
+        assert!(rules.check_add(&childs, ["rect"]).is_ok());
+        assert!(rules.check_add(&childs, ["line", "circle"]).is_ok());
+        assert!(matches!(
+            rules.check_add(&childs, ["rect", "rect"]),
+            Err(FyError::RuleViolated(_))
+        ));
+    }
+}